@@ -2,11 +2,10 @@
 extern crate vulkano;
 // #[macro_use]
 // extern crate vulkano_shader_derive;
+extern crate vulkan_helpers;
 extern crate vulkano_win;
 extern crate winit;
 
-mod vulkan_helpers;
-
 use vulkan_helpers::*;
 use vulkano::swapchain::Surface;
 use std::sync::Arc;
@@ -17,13 +16,22 @@ struct Application {
 }
 
 impl Application {
-    fn new() -> Application {
+    fn new() -> Result<Application, VulkanInitError> {
         let events_loop = init_events_loop();
-        let surface = init_vulkan(&events_loop);
-        Application {
+        let instance = init_vulkan_instance(&init_vulkan_layers()?)?;
+        init_vulkan_debug_callbacks(instance.clone());
+        let window = init_window([800, 600], "Vulkan");
+        let surface = init_surface(&events_loop, instance.clone(), window)?;
+        init_device(
+            instance.clone(),
+            surface.clone(),
+            &DeviceRequirements::default(),
+            DeviceSelector::from_env_or_args().as_ref(),
+        )?;
+        Ok(Application {
             events_loop,
             surface,
-        }
+        })
     }
     fn run(&mut self) {
         self.main_loop();
@@ -46,6 +54,12 @@ impl Application {
 }
 
 fn main() {
-    let mut app = Application::new();
+    let mut app = match Application::new() {
+        Ok(app) => app,
+        Err(err) => {
+            eprintln!("Failed to initialize Vulkan: {}", err);
+            std::process::exit(1);
+        }
+    };
     app.run();
 }
@@ -2,11 +2,12 @@
 extern crate vulkano;
 // #[macro_use]
 // extern crate vulkano_shader_derive;
+extern crate vulkan_helpers;
 extern crate vulkano_win;
 extern crate winit;
 
-mod vulkan_helpers;
-
+use std::thread;
+use std::time::Duration;
 use vulkan_helpers::*;
 
 struct Application {
@@ -15,13 +16,13 @@ struct Application {
 }
 
 impl Application {
-    fn new() -> Application {
+    fn new() -> Result<Application, VulkanInitError> {
         let events_loop = init_events_loop();
-        let vulkan_context = init_vulkan(&events_loop);
-        Application {
+        let vulkan_context = init_vulkan(&events_loop)?;
+        Ok(Application {
             events_loop,
             vulkan_context,
-        }
+        })
     }
     fn run(&mut self) {
         self.main_loop();
@@ -29,21 +30,47 @@ impl Application {
     fn main_loop(&mut self) {
         loop {
             let mut done = false;
+            let mut resized = false;
             self.events_loop.poll_events(|ev| match ev {
                 winit::Event::WindowEvent {
                     event: winit::WindowEvent::Closed,
                     ..
                 } => done = true,
+                winit::Event::WindowEvent {
+                    event: winit::WindowEvent::Resized(_, _),
+                    ..
+                } => resized = true,
                 _ => (),
             });
             if done {
                 return;
             }
+            if resized {
+                self.vulkan_context.invalidate_swapchain();
+            }
+            match self.vulkan_context.recreate_swapchain() {
+                Ok(true) => (),
+                Ok(false) => {
+                    // Minimized, nothing to draw until the window comes back.
+                    thread::sleep(Duration::from_millis(100));
+                    continue;
+                }
+                Err(err) => {
+                    eprintln!("Failed to recreate swapchain: {}", err);
+                    return;
+                }
+            }
         }
     }
 }
 
 fn main() {
-    let mut app = Application::new();
+    let mut app = match Application::new() {
+        Ok(app) => app,
+        Err(err) => {
+            eprintln!("Failed to initialize Vulkan: {}", err);
+            std::process::exit(1);
+        }
+    };
     app.run();
 }
@@ -0,0 +1,100 @@
+extern crate glsl_preprocess;
+extern crate glsl_to_spirv;
+
+use glsl_preprocess::{preprocess, Diagnostic, Preprocessed};
+use glsl_to_spirv::ShaderType;
+use std::env;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+
+//INFO (danny): Compiles everything in `shaders/` to SPIR-V so the binary doesn't care where it's run from.
+// `src/main.rs` pulls the bytes in through `$OUT_DIR/shaders.rs`.
+fn main() {
+    let shader_dir = Path::new("shaders");
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+    println!("cargo:rerun-if-changed={}", shader_dir.display());
+
+    let mut paths: Vec<PathBuf> = fs::read_dir(shader_dir)
+        .expect("Could not read shaders directory!")
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| shader_type(path).is_some())
+        .collect();
+    paths.sort();
+
+    let mut shaders_rs = String::new();
+    let mut failed = false;
+    for path in &paths {
+        println!("cargo:rerun-if-changed={}", path.display());
+        let file_name = path.file_name().unwrap().to_string_lossy().into_owned();
+        let source = fs::read_to_string(path).expect("Could not read shader file!");
+        let preprocessed = match preprocess(path, &source, shader_dir, &[]) {
+            Ok(preprocessed) => preprocessed,
+            Err(err) => {
+                failed = true;
+                eprintln!("error: {}", err);
+                continue;
+            }
+        };
+        for include in &preprocessed.includes {
+            println!("cargo:rerun-if-changed={}", include.display());
+        }
+        match glsl_to_spirv::compile(&preprocessed.source, shader_type(path).unwrap()) {
+            Ok(mut spirv) => {
+                let mut bytes = Vec::new();
+                spirv.read_to_end(&mut bytes).unwrap();
+                let spv_name = format!("{}.spv", file_name);
+                fs::write(out_dir.join(&spv_name), &bytes).unwrap();
+                shaders_rs.push_str(&format!(
+                    "pub const {}: &[u8] = include_bytes!(concat!(env!(\"OUT_DIR\"), \"/{}\"));\n",
+                    const_name(&file_name),
+                    spv_name,
+                ));
+            }
+            Err(message) => {
+                failed = true;
+                report(path, &preprocessed, &message);
+            }
+        }
+    }
+    if failed {
+        process::exit(1);
+    }
+    fs::File::create(out_dir.join("shaders.rs"))
+        .and_then(|mut file| file.write_all(shaders_rs.as_bytes()))
+        .unwrap();
+}
+
+fn shader_type(path: &Path) -> Option<ShaderType> {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("vert") => Some(ShaderType::Vertex),
+        Some("frag") => Some(ShaderType::Fragment),
+        Some("geom") => Some(ShaderType::Geometry),
+        Some("tesc") => Some(ShaderType::TessellationControl),
+        Some("tese") => Some(ShaderType::TessellationEvaluation),
+        Some("comp") => Some(ShaderType::Compute),
+        _ => None,
+    }
+}
+
+/// `shader.vert` becomes `SHADER_VERT`.
+fn const_name(file_name: &str) -> String {
+    file_name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+        .collect()
+}
+
+/// glslang reports against the temp file it was handed, so point the lines back at our file,
+/// or whichever include they came from.
+fn report(path: &Path, preprocessed: &Preprocessed, message: &str) {
+    let file = path.display().to_string();
+    let diagnostics = Diagnostic::parse_preprocessed(&file, preprocessed, message);
+    if diagnostics.is_empty() {
+        eprintln!("{}: error: {}", file, message.trim());
+    }
+    for diagnostic in diagnostics {
+        eprint!("{}", diagnostic.render());
+    }
+}
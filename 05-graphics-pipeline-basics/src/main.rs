@@ -1,26 +1,42 @@
+extern crate vulkan_helpers;
 extern crate vulkano;
-extern crate vulkano_shaders;
-extern crate glsl_to_spirv;
 extern crate vulkano_win;
 extern crate winit;
 
-mod vulkan_helpers;
-
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
 use vulkan_helpers::*;
+use vulkano::command_buffer::{AutoCommandBufferBuilder, DynamicState};
+use vulkano::framebuffer::FramebufferAbstract;
+use vulkano::pipeline::GraphicsPipelineAbstract;
+use vulkano::pipeline::vertex::BufferlessVertices;
+
+/// SPIR-V compiled from `shaders/` by `build.rs`.
+mod shaders {
+    include!(concat!(env!("OUT_DIR"), "/shaders.rs"));
+}
 
 struct Application {
     events_loop: winit::EventsLoop,
     vulkan_context: Box<VulkanContext>,
+    frames_in_flight: FramesInFlight,
+    shader_watcher: ShaderWatcher,
 }
 
 impl Application {
-    fn new() -> Application {
+    fn new() -> Result<Application, VulkanInitError> {
         let events_loop = init_events_loop();
-        let vulkan_context = init_vulkan(&events_loop);
-        Application {
+        let vulkan_context = VulkanContextBuilder::new()
+            .with_pipeline(triangle_pipeline())
+            .build(&events_loop)?;
+        Ok(Application {
             events_loop,
             vulkan_context,
-        }
+            frames_in_flight: FramesInFlight::new(DEFAULT_FRAMES_IN_FLIGHT),
+            // Only finds anything when run from a checkout, which is where you'd be editing shaders anyway.
+            shader_watcher: ShaderWatcher::new(concat!(env!("CARGO_MANIFEST_DIR"), "/shaders")),
+        })
     }
     fn run(&mut self) {
         self.main_loop();
@@ -28,21 +44,94 @@ impl Application {
     fn main_loop(&mut self) {
         loop {
             let mut done = false;
+            let mut resized = false;
             self.events_loop.poll_events(|ev| match ev {
                 winit::Event::WindowEvent {
                     event: winit::WindowEvent::Closed,
                     ..
                 } => done = true,
+                winit::Event::WindowEvent {
+                    event: winit::WindowEvent::Resized(_, _),
+                    ..
+                } => resized = true,
                 _ => (),
             });
             if done {
                 return;
             }
+            if resized {
+                self.vulkan_context.invalidate_swapchain();
+            }
+            for path in self.shader_watcher.changed() {
+                match self.vulkan_context.reload_shader(&path) {
+                    Ok(true) => println!("Reloaded {}", path.display()),
+                    Ok(false) => (),
+                    Err(err) => eprintln!("Keeping the old pipeline: {}", err),
+                }
+            }
+            match self.frames_in_flight
+                .draw_frame(&mut self.vulkan_context, record_triangle)
+            {
+                Ok(true) => (),
+                Ok(false) => if self.vulkan_context.is_minimized() {
+                    // Nothing to draw until the window comes back.
+                    thread::sleep(Duration::from_millis(100));
+                },
+                Err(err) => {
+                    eprintln!("Failed to draw frame: {}", err);
+                    return;
+                }
+            }
         }
     }
 }
 
+/// Clears to black and draws the triangle, whose vertices live in `shader.vert`.
+fn record_triangle(
+    builder: AutoCommandBufferBuilder,
+    framebuffer: Arc<FramebufferAbstract + Send + Sync>,
+    pipeline: Option<Arc<GraphicsPipelineAbstract + Send + Sync>>,
+) -> Result<AutoCommandBufferBuilder, FrameError> {
+    let clear_values = vec![[0.0, 0.0, 0.0, 1.0].into()];
+    let mut builder = builder.begin_render_pass(framebuffer, false, clear_values)?;
+    if let Some(pipeline) = pipeline {
+        builder = builder.draw(
+            pipeline,
+            DynamicState::none(),
+            BufferlessVertices {
+                vertices: 3,
+                instances: 1,
+            },
+            (),
+            (),
+        )?;
+    }
+    Ok(builder.end_render_pass()?)
+}
+
+fn triangle_pipeline() -> PipelineDesc {
+    PipelineDesc {
+        vertex_shader: ShaderSource::Spirv {
+            name: String::from("shader.vert"),
+            bytes: shaders::SHADER_VERT.to_vec(),
+        },
+        fragment_shader: ShaderSource::Spirv {
+            name: String::from("shader.frag"),
+            bytes: shaders::SHADER_FRAG.to_vec(),
+        },
+        interface: None,
+        compile_options: CompileOptions::default(),
+        specialization: None,
+    }
+}
+
 fn main() {
-    let mut app = Application::new();
+    let mut app = match Application::new() {
+        Ok(app) => app,
+        Err(err) => {
+            eprintln!("Failed to initialize Vulkan: {}", err);
+            std::process::exit(1);
+        }
+    };
     app.run();
 }
@@ -0,0 +1,559 @@
+use std::collections::{HashMap, HashSet};
+
+/// How far a macro can expand into other macros before we give up on it.
+const MAX_EXPANSION_DEPTH: u32 = 16;
+
+/// Whether the lines under an `#if` make it to glslang.
+///
+/// We only need this to know which `#include`s to expand, glslang still does the real conditional
+/// compilation. `Unknown` is for conditions we can't evaluate, like ones on glslang's own `GL_` macros.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Branch {
+    Active,
+    Skipped,
+    Unknown,
+}
+
+impl Branch {
+    /// The branch a condition opens inside `self`.
+    fn enter(self, condition: Option<bool>) -> Branch {
+        match (self, condition) {
+            (Branch::Skipped, _) | (_, Some(false)) => Branch::Skipped,
+            (_, None) => Branch::Unknown,
+            (outer, Some(true)) => outer,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Conditional {
+    outer: Branch,
+    current: Branch,
+    /// Whether an earlier branch of this `#if` was taken, `None` if it might have been.
+    taken: Option<bool>,
+}
+
+/// The macros defined so far and the `#if`s currently open, enough to follow `#ifdef FEATURE` blocks.
+#[derive(Debug)]
+pub struct Conditionals {
+    /// Object-like macros with their bodies, function-like ones with `None`.
+    macros: HashMap<String, Option<String>>,
+    /// Macros defined or undefined in an `Unknown` branch, so they might or might not exist.
+    uncertain: HashSet<String>,
+    open: Vec<Conditional>,
+}
+
+impl Conditionals {
+    /// Starts out with what `glslangValidator -V` defines for every shader.
+    pub fn new(defines: &[(String, String)]) -> Conditionals {
+        let mut conditionals = Conditionals {
+            macros: HashMap::new(),
+            uncertain: HashSet::new(),
+            open: vec![],
+        };
+        conditionals.define("VULKAN", Some("100"));
+        conditionals.define("GL_SPIRV", Some("100"));
+        for (name, value) in defines {
+            conditionals.define(name, Some(value));
+        }
+        conditionals
+    }
+
+    pub fn branch(&self) -> Branch {
+        self.open.last().map_or(Branch::Active, |conditional| conditional.current)
+    }
+
+    /// Follows a directive, ignoring the ones that don't define anything or open or close a branch.
+    pub fn directive(&mut self, name: &str, rest: &str) {
+        match name {
+            "if" => {
+                let condition = self.evaluate(rest);
+                self.open(condition);
+            }
+            "ifdef" => {
+                let condition = self.is_defined(rest.trim());
+                self.open(condition);
+            }
+            "ifndef" => {
+                let condition = self.is_defined(rest.trim()).map(|defined| !defined);
+                self.open(condition);
+            }
+            "elif" => {
+                let condition = self.evaluate(rest);
+                self.next_branch(condition);
+            }
+            "else" => self.next_branch(Some(true)),
+            "endif" => {
+                self.open.pop();
+            }
+            "define" => {
+                let (name, body) = split_define(rest);
+                self.define_in_branch(name, body);
+            }
+            "undef" => {
+                let name = rest.trim();
+                match self.branch() {
+                    Branch::Active => {
+                        self.macros.remove(name);
+                        self.uncertain.remove(name);
+                    }
+                    Branch::Unknown => {
+                        self.uncertain.insert(name.to_string());
+                    }
+                    Branch::Skipped => (),
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn open(&mut self, condition: Option<bool>) {
+        let outer = self.branch();
+        self.open.push(Conditional {
+            outer,
+            current: outer.enter(condition),
+            taken: condition,
+        });
+    }
+
+    fn next_branch(&mut self, condition: Option<bool>) {
+        let conditional = match self.open.last_mut() {
+            Some(conditional) => conditional,
+            // glslang reports the stray `#elif` or `#else` itself.
+            None => return,
+        };
+        let condition = match (conditional.taken, condition) {
+            (Some(true), _) => Some(false),
+            (Some(false), condition) => condition,
+            (None, Some(false)) => Some(false),
+            (None, _) => None,
+        };
+        conditional.current = conditional.outer.enter(condition);
+        conditional.taken = match (conditional.taken, condition) {
+            (Some(true), _) | (_, Some(true)) => Some(true),
+            (Some(false), Some(false)) => Some(false),
+            _ => None,
+        };
+    }
+
+    fn define_in_branch(&mut self, name: &str, body: Option<&str>) {
+        match self.branch() {
+            Branch::Active => {
+                self.define(name, body);
+                self.uncertain.remove(name);
+            }
+            Branch::Unknown => {
+                self.uncertain.insert(name.to_string());
+            }
+            Branch::Skipped => (),
+        }
+    }
+
+    pub fn define(&mut self, name: &str, body: Option<&str>) {
+        self.macros
+            .insert(name.to_string(), body.map(|body| body.trim().to_string()));
+    }
+
+    fn is_defined(&self, name: &str) -> Option<bool> {
+        if self.uncertain.contains(name) {
+            None
+        } else if self.macros.contains_key(name) {
+            Some(true)
+        } else if is_reserved(name) {
+            // glslang defines plenty of these itself, like one per supported extension.
+            None
+        } else {
+            Some(false)
+        }
+    }
+
+    /// `None` when the expression uses something we can't know, or doesn't parse.
+    fn evaluate(&self, expression: &str) -> Option<bool> {
+        self.evaluate_nested(expression, 0)
+            .unwrap_or(None)
+            .map(|value| value != 0)
+    }
+
+    fn evaluate_nested(&self, expression: &str, depth: u32) -> Result<Option<i64>, ()> {
+        if depth > MAX_EXPANSION_DEPTH {
+            return Ok(None);
+        }
+        let tokens = tokenize(expression)?;
+        let mut parser = Parser {
+            conditionals: self,
+            tokens: &tokens,
+            position: 0,
+            depth,
+        };
+        let value = parser.binary(0)?;
+        if parser.position == tokens.len() {
+            Ok(value)
+        } else {
+            Err(())
+        }
+    }
+
+    /// The value of an identifier in an `#if`. Undefined ones are 0, as in C.
+    fn value(&self, name: &str, depth: u32) -> Result<Option<i64>, ()> {
+        if self.uncertain.contains(name) {
+            return Ok(None);
+        }
+        match self.macros.get(name) {
+            Some(Some(body)) if !body.is_empty() => self.evaluate_nested(body, depth + 1),
+            Some(_) => Ok(None),
+            None if is_reserved(name) => Ok(None),
+            None => Ok(Some(0)),
+        }
+    }
+}
+
+/// `GL_` and `__` names belong to the compiler, so their absence here doesn't mean they're undefined.
+fn is_reserved(name: &str) -> bool {
+    name.starts_with("GL_") || name.starts_with("__")
+}
+
+/// `NAME body` for object-like macros, `NAME(args) body` gives no body since we don't expand those.
+fn split_define(rest: &str) -> (&str, Option<&str>) {
+    let rest = rest.trim_start();
+    let end = rest
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .unwrap_or(rest.len());
+    let (name, body) = rest.split_at(end);
+    if body.starts_with('(') {
+        (name, None)
+    } else {
+        (name, Some(body))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(i64),
+    Identifier(String),
+    Operator(&'static str),
+}
+
+const OPERATORS: &[&str] = &[
+    "||", "&&", "==", "!=", "<=", ">=", "<<", ">>", "(", ")", "!", "~", "*", "/", "%", "+", "-",
+    "<", ">", "&", "^", "|",
+];
+
+fn tokenize(expression: &str) -> Result<Vec<Token>, ()> {
+    let mut tokens = vec![];
+    let mut rest = expression.trim_start();
+    while !rest.is_empty() {
+        let first = rest.chars().next().unwrap();
+        if first.is_ascii_digit() {
+            let end = rest
+                .find(|c: char| !c.is_ascii_alphanumeric())
+                .unwrap_or(rest.len());
+            tokens.push(Token::Number(parse_number(&rest[..end])?));
+            rest = &rest[end..];
+        } else if first.is_ascii_alphabetic() || first == '_' {
+            let end = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            tokens.push(Token::Identifier(rest[..end].to_string()));
+            rest = &rest[end..];
+        } else {
+            let operator = OPERATORS
+                .iter()
+                .find(|operator| rest.starts_with(*operator))
+                .ok_or(())?;
+            tokens.push(Token::Operator(operator));
+            rest = &rest[operator.len()..];
+        }
+        rest = rest.trim_start();
+    }
+    Ok(tokens)
+}
+
+/// Decimal, `0x` hex or `0` octal, with an optional `u` suffix.
+fn parse_number(literal: &str) -> Result<i64, ()> {
+    let literal = literal.trim_end_matches(['u', 'U']);
+    let parsed = if literal.starts_with("0x") || literal.starts_with("0X") {
+        i64::from_str_radix(&literal[2..], 16)
+    } else if literal.len() > 1 && literal.starts_with('0') {
+        i64::from_str_radix(&literal[1..], 8)
+    } else {
+        literal.parse()
+    };
+    parsed.map_err(|_| ())
+}
+
+struct Parser<'a> {
+    conditionals: &'a Conditionals,
+    tokens: &'a [Token],
+    position: usize,
+    depth: u32,
+}
+
+impl<'a> Parser<'a> {
+    fn next(&mut self) -> Option<&'a Token> {
+        let token = self.tokens.get(self.position);
+        self.position += 1;
+        token
+    }
+
+    fn peek_operator(&self) -> Option<&'static str> {
+        match self.tokens.get(self.position) {
+            Some(&Token::Operator(operator)) => Some(operator),
+            _ => None,
+        }
+    }
+
+    fn expect(&mut self, operator: &str) -> Result<(), ()> {
+        match self.next() {
+            Some(&Token::Operator(found)) if found == operator => Ok(()),
+            _ => Err(()),
+        }
+    }
+
+    /// Precedence climbing over C's binary operators, lowest precedence first.
+    fn binary(&mut self, min_precedence: u32) -> Result<Option<i64>, ()> {
+        let mut left = self.unary()?;
+        while let Some(operator) = self.peek_operator() {
+            let precedence = match precedence(operator) {
+                Some(precedence) if precedence >= min_precedence => precedence,
+                _ => break,
+            };
+            self.position += 1;
+            let right = self.binary(precedence + 1)?;
+            left = apply(operator, left, right);
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Option<i64>, ()> {
+        match self.next() {
+            Some(&Token::Number(value)) => Ok(Some(value)),
+            Some(Token::Identifier(name)) if name == "defined" => {
+                let parenthesized = self.peek_operator() == Some("(");
+                if parenthesized {
+                    self.position += 1;
+                }
+                let defined = match self.next() {
+                    Some(Token::Identifier(name)) => self.conditionals.is_defined(name),
+                    _ => return Err(()),
+                };
+                if parenthesized {
+                    self.expect(")")?;
+                }
+                Ok(defined.map(i64::from))
+            }
+            Some(Token::Identifier(name)) => self.conditionals.value(name, self.depth),
+            Some(&Token::Operator("(")) => {
+                let value = self.binary(0)?;
+                self.expect(")")?;
+                Ok(value)
+            }
+            Some(&Token::Operator(operator)) if ["!", "~", "-", "+"].contains(&operator) => {
+                let value = self.unary()?;
+                Ok(value.map(|value| match operator {
+                    "!" => i64::from(value == 0),
+                    "~" => !value,
+                    "-" => value.wrapping_neg(),
+                    _ => value,
+                }))
+            }
+            _ => Err(()),
+        }
+    }
+}
+
+fn precedence(operator: &str) -> Option<u32> {
+    Some(match operator {
+        "||" => 0,
+        "&&" => 1,
+        "|" => 2,
+        "^" => 3,
+        "&" => 4,
+        "==" | "!=" => 5,
+        "<" | ">" | "<=" | ">=" => 6,
+        "<<" | ">>" => 7,
+        "+" | "-" => 8,
+        "*" | "/" | "%" => 9,
+        _ => return None,
+    })
+}
+
+/// `||` and `&&` still have an answer when one side is unknown but the other decides it.
+fn apply(operator: &str, left: Option<i64>, right: Option<i64>) -> Option<i64> {
+    match operator {
+        "||" => match (left, right) {
+            (Some(left), _) if left != 0 => Some(1),
+            (_, Some(right)) if right != 0 => Some(1),
+            (Some(_), Some(_)) => Some(0),
+            _ => None,
+        },
+        "&&" => match (left, right) {
+            (Some(0), _) | (_, Some(0)) => Some(0),
+            (Some(_), Some(_)) => Some(1),
+            _ => None,
+        },
+        _ => {
+            let (left, right) = (left?, right?);
+            Some(match operator {
+                "|" => left | right,
+                "^" => left ^ right,
+                "&" => left & right,
+                "==" => i64::from(left == right),
+                "!=" => i64::from(left != right),
+                "<" => i64::from(left < right),
+                ">" => i64::from(left > right),
+                "<=" => i64::from(left <= right),
+                ">=" => i64::from(left >= right),
+                "<<" => left.checked_shl(right as u32)?,
+                ">>" => left.checked_shr(right as u32)?,
+                "+" => left.wrapping_add(right),
+                "-" => left.wrapping_sub(right),
+                "*" => left.wrapping_mul(right),
+                "/" => left.checked_div(right)?,
+                "%" => left.checked_rem(right)?,
+                _ => return None,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The branch after following `directives`, each written as it would appear after the `#`.
+    fn branch_after(defines: &[(&str, &str)], directives: &[&str]) -> Branch {
+        let defines: Vec<(String, String)> = defines
+            .iter()
+            .map(|&(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        let mut conditionals = Conditionals::new(&defines);
+        for directive in directives {
+            let (name, rest) =
+                directive.split_at(directive.find(' ').unwrap_or(directive.len()));
+            conditionals.directive(name, rest);
+        }
+        conditionals.branch()
+    }
+
+    #[test]
+    fn ifdef_follows_defines() {
+        assert_eq!(
+            branch_after(&[("USE_TEXTURE", "1")], &["ifdef USE_TEXTURE"]),
+            Branch::Active
+        );
+        assert_eq!(branch_after(&[], &["ifdef USE_TEXTURE"]), Branch::Skipped);
+        assert_eq!(branch_after(&[], &["ifndef USE_TEXTURE"]), Branch::Active);
+        assert_eq!(branch_after(&[], &["ifdef VULKAN"]), Branch::Active);
+        assert_eq!(
+            branch_after(&[], &["ifdef USE_TEXTURE", "endif"]),
+            Branch::Active
+        );
+    }
+
+    #[test]
+    fn else_and_elif_take_one_branch() {
+        assert_eq!(branch_after(&[], &["ifdef A", "else"]), Branch::Active);
+        assert_eq!(
+            branch_after(&[("A", "1")], &["ifdef A", "else"]),
+            Branch::Skipped
+        );
+        assert_eq!(
+            branch_after(&[("B", "2")], &["if A", "elif B == 2"]),
+            Branch::Active
+        );
+        assert_eq!(
+            branch_after(&[("A", "1"), ("B", "2")], &["if A", "elif B == 2"]),
+            Branch::Skipped
+        );
+        assert_eq!(
+            branch_after(&[("A", "1")], &["if A", "elif 1", "else"]),
+            Branch::Skipped
+        );
+    }
+
+    #[test]
+    fn nested_branches_stay_skipped() {
+        assert_eq!(
+            branch_after(&[("B", "1")], &["ifdef A", "ifdef B"]),
+            Branch::Skipped
+        );
+        assert_eq!(
+            branch_after(&[], &["ifdef A", "ifdef B", "else"]),
+            Branch::Skipped
+        );
+        assert_eq!(
+            branch_after(&[], &["ifdef A", "ifdef B", "endif", "else"]),
+            Branch::Active
+        );
+    }
+
+    #[test]
+    fn if_expressions_are_evaluated() {
+        let defines = &[("QUALITY", "2"), ("LEVEL", "QUALITY + 1")];
+        assert_eq!(
+            branch_after(defines, &["if QUALITY >= 2 && defined(QUALITY)"]),
+            Branch::Active
+        );
+        assert_eq!(
+            branch_after(defines, &["if LEVEL * 2 == 6"]),
+            Branch::Active
+        );
+        assert_eq!(
+            branch_after(defines, &["if (QUALITY << 1) > 8 || !defined QUALITY"]),
+            Branch::Skipped
+        );
+        assert_eq!(branch_after(defines, &["if MISSING"]), Branch::Skipped);
+        assert_eq!(
+            branch_after(defines, &["if 0x10 - 017 == 1"]),
+            Branch::Active
+        );
+    }
+
+    #[test]
+    fn compiler_macros_are_unknown() {
+        assert_eq!(
+            branch_after(&[], &["ifdef GL_EXT_nonuniform_qualifier"]),
+            Branch::Unknown
+        );
+        assert_eq!(
+            branch_after(&[], &["if __VERSION__ >= 450"]),
+            Branch::Unknown
+        );
+        assert_eq!(branch_after(&[], &["if GL_ARB_foo || 1"]), Branch::Active);
+        assert_eq!(branch_after(&[], &["if GL_ARB_foo && 0"]), Branch::Skipped);
+        assert_eq!(
+            branch_after(&[], &["ifdef GL_ARB_foo", "else"]),
+            Branch::Unknown
+        );
+    }
+
+    #[test]
+    fn unparseable_conditions_are_unknown() {
+        assert_eq!(branch_after(&[], &["if 1 +"]), Branch::Unknown);
+        assert_eq!(branch_after(&[], &["if FN(1)"]), Branch::Unknown);
+    }
+
+    #[test]
+    fn defines_and_undefs_are_followed() {
+        assert_eq!(branch_after(&[], &["define A", "ifdef A"]), Branch::Active);
+        assert_eq!(
+            branch_after(&[("A", "1")], &["undef A", "ifdef A"]),
+            Branch::Skipped
+        );
+        // Only defined if the unknown branch is taken.
+        assert_eq!(
+            branch_after(&[], &["ifdef GL_ARB_foo", "define A", "endif", "ifdef A"]),
+            Branch::Unknown
+        );
+        // Defines in a skipped branch don't happen.
+        assert_eq!(
+            branch_after(&[], &["ifdef B", "define A", "endif", "ifdef A"]),
+            Branch::Skipped
+        );
+    }
+
+    #[test]
+    fn self_referencing_macros_give_up() {
+        assert_eq!(branch_after(&[("A", "A + 1")], &["if A"]), Branch::Unknown);
+    }
+}
@@ -0,0 +1,249 @@
+use std::fmt;
+
+use preprocess::Preprocessed;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+/// One message from the GLSL compiler, pointed at our file rather than glslang's temp file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub file: String,
+    pub line: Option<u32>,
+    pub column: Option<u32>,
+    pub severity: Severity,
+    pub message: String,
+    /// The offending line, kept so it can be shown after `file` and `line` are pointed back at an include.
+    pub source_line: Option<String>,
+}
+
+impl Diagnostic {
+    /// Parses glslang's output, keeping the `ERROR:` and `WARNING:` lines.
+    ///
+    /// glslang prints `ERROR: <file>:<line>: '<token>' : <message>`, where `<file>` is
+    /// whatever temp file it was handed, so it gets replaced with `file`.
+    /// There's no column, so it's guessed from where `<token>` shows up on the line.
+    pub fn parse(file: &str, source: &str, output: &str) -> Vec<Diagnostic> {
+        output
+            .lines()
+            .filter_map(|line| {
+                let (severity, rest) = if let Some(rest) = line.strip_prefix("ERROR: ") {
+                    (Severity::Error, rest)
+                } else if let Some(rest) = line.strip_prefix("WARNING: ") {
+                    (Severity::Warning, rest)
+                } else {
+                    return None;
+                };
+                let (line_number, message) = match split_location(rest) {
+                    Some((line_number, message)) => (Some(line_number), message),
+                    // The "N compilation errors. No code generated." summary.
+                    None if rest.contains("compilation errors") => return None,
+                    None => (None, rest.trim()),
+                };
+                let source_line =
+                    line_number.and_then(|line_number| source.lines().nth(line_number as usize - 1));
+                let column = source_line.and_then(|source_line| guess_column(source_line, message));
+                Some(Diagnostic {
+                    file: file.to_string(),
+                    line: line_number,
+                    column,
+                    severity,
+                    message: message.to_string(),
+                    source_line: source_line.map(|source_line| source_line.to_string()),
+                })
+            })
+            .collect()
+    }
+
+    /// `parse` for glslang's output on preprocessed source, with each diagnostic pointed back at
+    /// the file and line it came from before includes were expanded.
+    pub fn parse_preprocessed(file: &str, preprocessed: &Preprocessed, output: &str) -> Vec<Diagnostic> {
+        let mut diagnostics = Diagnostic::parse(file, &preprocessed.source, output);
+        for diagnostic in &mut diagnostics {
+            let origin = diagnostic.line.and_then(|line| preprocessed.origin(line));
+            if let Some((origin_file, line)) = origin {
+                diagnostic.file = origin_file.display().to_string();
+                diagnostic.line = Some(line);
+            }
+        }
+        diagnostics
+    }
+
+    /// The message with the offending source line and a caret under it, rustc style.
+    pub fn render(&self) -> String {
+        let mut rendered = format!("{}: {}\n", self.severity, self.message);
+        let line_number = match self.line {
+            Some(line_number) => line_number,
+            None => {
+                rendered.push_str(&format!(" --> {}\n", self.file));
+                return rendered;
+            }
+        };
+        let column = self.column.unwrap_or(1);
+        rendered.push_str(&format!(" --> {}:{}:{}\n", self.file, line_number, column));
+        if let Some(ref source_line) = self.source_line {
+            let gutter = " ".repeat(line_number.to_string().len());
+            let caret_len = token(&self.message).map_or(1, |token| token.len().max(1));
+            rendered.push_str(&format!("{} |\n", gutter));
+            rendered.push_str(&format!("{} | {}\n", line_number, source_line));
+            rendered.push_str(&format!(
+                "{} | {}{}\n",
+                gutter,
+                " ".repeat(column as usize - 1),
+                "^".repeat(caret_len),
+            ));
+        }
+        rendered
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.file)?;
+        if let Some(line) = self.line {
+            write!(f, ":{}", line)?;
+        }
+        if let Some(column) = self.column {
+            write!(f, ":{}", column)?;
+        }
+        write!(f, ": {}: {}", self.severity, self.message)
+    }
+}
+
+/// Splits `<file>:<line>: <message>` or `<file>:<line>:<column>: <message>`
+/// without caring what `<file>` looks like. The column is dropped, glslang doesn't give a useful one.
+fn split_location(rest: &str) -> Option<(u32, &str)> {
+    for (i, _) in rest.match_indices(':') {
+        let after = &rest[i + 1..];
+        if let Some(end) = after.find(':') {
+            if let Ok(line_number) = after[..end].trim().parse::<u32>() {
+                if line_number > 0 {
+                    return Some((line_number, after[end + 1..].trim()));
+                }
+            }
+        }
+    }
+    None
+}
+
+/// The `'token'` glslang quotes at the start of most messages.
+fn token(message: &str) -> Option<&str> {
+    if !message.starts_with('\'') {
+        return None;
+    }
+    let end = message[1..].find('\'')?;
+    let token = &message[1..end + 1];
+    if token.is_empty() {
+        None
+    } else {
+        Some(token)
+    }
+}
+
+fn guess_column(source_line: &str, message: &str) -> Option<u32> {
+    let index = match token(message) {
+        Some(token) => find_token(source_line, token)?,
+        None => source_line.len() - source_line.trim_start().len(),
+    };
+    Some(source_line[..index].chars().count() as u32 + 1)
+}
+
+/// Where `token` shows up on its own, so `tex` isn't found inside `texture`.
+/// Falls back to anywhere on the line for tokens that aren't identifiers.
+fn find_token(source_line: &str, token: &str) -> Option<usize> {
+    let is_word = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    source_line
+        .match_indices(token)
+        .map(|(index, _)| index)
+        .find(|&index| {
+            let joins_before = token.starts_with(is_word) && source_line[..index].ends_with(is_word);
+            let joins_after = token.ends_with(is_word) && source_line[index + token.len()..].starts_with(is_word);
+            !joins_before && !joins_after
+        })
+        .or_else(|| source_line.find(token))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "#version 450\nvoid main() {\n    vec4 color = texture(tex, uv);\n}\n";
+
+    #[test]
+    fn parses_errors_and_warnings() {
+        let output = "/tmp/.tmpA1b2C3:3: 'tex' : undeclared identifier\n\
+                      ERROR: /tmp/.tmpA1b2C3:3: 'tex' : undeclared identifier\n\
+                      WARNING: C:\\Users\\me\\tmp.frag:1: '#version' : deprecated\n\
+                      ERROR: 1 compilation errors.  No code generated.\n";
+        let diagnostics = Diagnostic::parse("shaders/shader.frag", SOURCE, output);
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(
+            diagnostics[0],
+            Diagnostic {
+                file: "shaders/shader.frag".to_string(),
+                line: Some(3),
+                column: Some(26),
+                severity: Severity::Error,
+                message: "'tex' : undeclared identifier".to_string(),
+                source_line: Some("    vec4 color = texture(tex, uv);".to_string()),
+            }
+        );
+        assert_eq!(diagnostics[1].severity, Severity::Warning);
+        assert_eq!(diagnostics[1].line, Some(1));
+        assert_eq!(diagnostics[1].column, Some(1));
+    }
+
+    #[test]
+    fn messages_without_a_line_are_kept() {
+        let diagnostics = Diagnostic::parse(
+            "shader.frag",
+            SOURCE,
+            "ERROR: Linking fragment stage: Missing entry point\n",
+        );
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].line, None);
+        assert_eq!(
+            diagnostics[0].message,
+            "Linking fragment stage: Missing entry point"
+        );
+        assert_eq!(
+            diagnostics[0].to_string(),
+            "shader.frag: error: Linking fragment stage: Missing entry point"
+        );
+    }
+
+    #[test]
+    fn column_without_a_token_is_the_first_non_blank() {
+        let diagnostics = Diagnostic::parse("shader.frag", SOURCE, "ERROR: 0:3: syntax error\n");
+        assert_eq!(diagnostics[0].column, Some(5));
+    }
+
+    #[test]
+    fn renders_with_a_caret() {
+        let diagnostics = Diagnostic::parse(
+            "shader.frag",
+            SOURCE,
+            "ERROR: 0:3: 'tex' : undeclared identifier\n",
+        );
+        assert_eq!(
+            diagnostics[0].render(),
+            "error: 'tex' : undeclared identifier\n \
+             --> shader.frag:3:26\n  \
+             |\n\
+             3 |     vec4 color = texture(tex, uv);\n  \
+             |                          ^^^\n"
+        );
+    }
+}
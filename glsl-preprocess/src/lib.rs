@@ -0,0 +1,10 @@
+// The GLSL preprocessing shared by `vulkan-helpers` at runtime and chapter build scripts,
+// so embedded and hot reloaded shaders resolve includes and report errors the same way.
+// Only uses std, so it's cheap as a build dependency.
+
+mod condition;
+mod diagnostic;
+mod preprocess;
+
+pub use diagnostic::{Diagnostic, Severity};
+pub use preprocess::{preprocess, IncludeError, Preprocessed};
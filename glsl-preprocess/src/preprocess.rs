@@ -0,0 +1,628 @@
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use condition::{Branch, Conditionals};
+
+/// GLSL with its `#include`s pasted in and defines injected, ready for glslang.
+#[derive(Debug, Clone)]
+pub struct Preprocessed {
+    pub source: String,
+    /// Every file that got pulled in, canonicalized, in the order they were first included.
+    pub includes: Vec<PathBuf>,
+    files: Vec<PathBuf>,
+    /// Index into `files` and line number for each line of `source`, `None` for injected defines.
+    origins: Vec<Option<(usize, u32)>>,
+}
+
+impl Preprocessed {
+    /// Which file and line a line of `source` came from, for pointing compiler errors back at it.
+    pub fn origin(&self, line: u32) -> Option<(&Path, u32)> {
+        if line == 0 {
+            return None;
+        }
+        self.origins
+            .get(line as usize - 1)
+            .and_then(|origin| *origin)
+            .map(|(file, line)| (self.files[file].as_path(), line))
+    }
+}
+
+#[derive(Debug)]
+pub enum IncludeError {
+    Read {
+        path: PathBuf,
+        error: io::Error,
+    },
+    NotFound {
+        file: PathBuf,
+        line: u32,
+        include: String,
+    },
+    Malformed {
+        file: PathBuf,
+        line: u32,
+    },
+    /// The include names a file outside the include root, like `<../../x>` or `</etc/passwd>`.
+    OutsideRoot {
+        file: PathBuf,
+        line: u32,
+        include: String,
+        resolved: PathBuf,
+    },
+    /// Each file in the chain includes the next, and the last one is the first one again.
+    Cycle {
+        chain: Vec<PathBuf>,
+    },
+}
+
+impl fmt::Display for IncludeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            IncludeError::Read {
+                ref path,
+                ref error,
+            } => write!(f, "could not read {}: {}", path.display(), error),
+            IncludeError::NotFound {
+                ref file,
+                line,
+                ref include,
+            } => write!(
+                f,
+                "{}:{}: could not find include \"{}\"",
+                file.display(),
+                line,
+                include
+            ),
+            IncludeError::Malformed { ref file, line } => write!(
+                f,
+                "{}:{}: expected #include \"file\" or #include <file>",
+                file.display(),
+                line
+            ),
+            IncludeError::OutsideRoot {
+                ref file,
+                line,
+                ref include,
+                ref resolved,
+            } => write!(
+                f,
+                "{}:{}: include \"{}\" resolves to {}, which is outside the include root",
+                file.display(),
+                line,
+                include,
+                resolved.display()
+            ),
+            IncludeError::Cycle { ref chain } => {
+                write!(f, "include cycle: ")?;
+                for (i, path) in chain.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " -> ")?;
+                    }
+                    write!(f, "{}", path.display())?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Error for IncludeError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            IncludeError::Read { ref error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// Expands `#include "file"` and `#include <file>` and adds a `#define NAME VALUE` per define
+/// right after `#version`.
+///
+/// `<file>` is looked up in `include_root`. `"file"` is looked up next to the file including it first,
+/// then in `include_root`. `#pragma once` works, and including a file from inside itself is an error.
+/// Whatever an include resolves to has to be inside `include_root` or the directory of `path`,
+/// so `..` and absolute paths can't pull in arbitrary files.
+///
+/// `#include`s in comments are left alone, and so are ones under an `#if`, `#ifdef` or `#ifndef`
+/// that's false for these defines, so a feature's include only has to exist when the feature is on.
+pub fn preprocess(
+    path: &Path,
+    source: &str,
+    include_root: &Path,
+    defines: &[(String, String)],
+) -> Result<Preprocessed, IncludeError> {
+    let mut roots = vec![canonical_dir(include_root)];
+    if let Some(dir) = path.parent() {
+        roots.push(canonical_dir(dir));
+    }
+    let mut preprocessor = Preprocessor {
+        include_root,
+        roots,
+        preprocessed: Preprocessed {
+            source: String::new(),
+            includes: vec![],
+            files: vec![],
+            origins: vec![],
+        },
+        stack: vec![],
+        once: HashSet::new(),
+        conditionals: Conditionals::new(defines),
+    };
+    preprocessor.expand(path, canonical(path), source, Some(defines))?;
+    Ok(preprocessor.preprocessed)
+}
+
+struct Preprocessor<'a> {
+    include_root: &'a Path,
+    /// Canonical directories includes have to stay inside.
+    roots: Vec<PathBuf>,
+    preprocessed: Preprocessed,
+    /// The files currently being expanded, outermost first.
+    stack: Vec<PathBuf>,
+    once: HashSet<PathBuf>,
+    conditionals: Conditionals,
+}
+
+impl<'a> Preprocessor<'a> {
+    fn expand(
+        &mut self,
+        path: &Path,
+        canonical_path: PathBuf,
+        source: &str,
+        defines: Option<&[(String, String)]>,
+    ) -> Result<(), IncludeError> {
+        if self.stack.contains(&canonical_path) {
+            let mut chain = self.stack.clone();
+            chain.push(canonical_path);
+            return Err(IncludeError::Cycle { chain });
+        }
+        self.stack.push(canonical_path.clone());
+        let file = self.preprocessed.files.len();
+        self.preprocessed.files.push(path.to_path_buf());
+
+        let mut defines = defines;
+        // NOTE: Nothing but comments may come before `#version`, so defines go right after it.
+        if !source.lines().any(|line| directive(line, "version").is_some()) {
+            self.push_defines(defines.take());
+        }
+        let mut in_comment = false;
+        for (index, line) in source.lines().enumerate() {
+            let line_number = index as u32 + 1;
+            let code = strip_comments(line, &mut in_comment);
+            let dropped = match parse_directive(&code) {
+                Some(("include", rest)) => {
+                    self.include(path, line, rest, (file, line_number))?;
+                    true
+                }
+                Some(("pragma", rest)) if rest.trim() == "once" => {
+                    if self.conditionals.branch() != Branch::Skipped {
+                        self.once.insert(canonical_path.clone());
+                    }
+                    true
+                }
+                Some((name, rest)) => {
+                    self.conditionals.directive(name, rest);
+                    false
+                }
+                None => false,
+            };
+            if dropped {
+                // Keep glslang inside a block comment that started on the dropped line.
+                if in_comment {
+                    self.push_line("/*", Some((file, line_number)));
+                }
+                continue;
+            }
+            self.push_line(line, Some((file, line_number)));
+            if let Some(rest) = directive(&code, "version") {
+                let version = rest.split_whitespace().next().unwrap_or("");
+                self.conditionals.define("__VERSION__", Some(version));
+                self.push_defines(defines.take());
+            }
+        }
+        self.stack.pop();
+        Ok(())
+    }
+
+    /// Pastes in the file an `#include` names, unless it's in a branch that's skipped.
+    fn include(
+        &mut self,
+        path: &Path,
+        line: &str,
+        rest: &str,
+        origin: (usize, u32),
+    ) -> Result<(), IncludeError> {
+        let branch = self.conditionals.branch();
+        if branch == Branch::Skipped {
+            return Ok(());
+        }
+        let line_number = origin.1;
+        let (include, quoted) = parse_include(rest).ok_or_else(|| IncludeError::Malformed {
+            file: path.to_path_buf(),
+            line: line_number,
+        })?;
+        let resolved = match self.resolve(path, include, quoted) {
+            Some(resolved) => resolved,
+            // It might never be compiled, so hand the line to glslang, which only complains if it gets there.
+            None if branch == Branch::Unknown => {
+                self.push_line(line, Some(origin));
+                return Ok(());
+            }
+            None => {
+                return Err(IncludeError::NotFound {
+                    file: path.to_path_buf(),
+                    line: line_number,
+                    include: include.to_string(),
+                })
+            }
+        };
+        let canonical_include = canonical(&resolved);
+        if !self.roots.iter().any(|root| canonical_include.starts_with(root)) {
+            return Err(IncludeError::OutsideRoot {
+                file: path.to_path_buf(),
+                line: line_number,
+                include: include.to_string(),
+                resolved: canonical_include,
+            });
+        }
+        if self.once.contains(&canonical_include) {
+            return Ok(());
+        }
+        let included = fs::read_to_string(&resolved).map_err(|error| IncludeError::Read {
+            path: resolved.clone(),
+            error,
+        })?;
+        if !self.preprocessed.includes.contains(&canonical_include) {
+            self.preprocessed.includes.push(canonical_include.clone());
+        }
+        self.expand(&resolved, canonical_include, &included, None)
+    }
+
+    fn resolve(&self, includer: &Path, include: &str, quoted: bool) -> Option<PathBuf> {
+        if quoted {
+            if let Some(dir) = includer.parent() {
+                let beside = dir.join(include);
+                if beside.is_file() {
+                    return Some(beside);
+                }
+            }
+        }
+        let rooted = self.include_root.join(include);
+        if rooted.is_file() {
+            Some(rooted)
+        } else {
+            None
+        }
+    }
+
+    fn push_defines(&mut self, defines: Option<&[(String, String)]>) {
+        for (name, value) in defines.unwrap_or(&[]) {
+            self.push_line(&format!("#define {} {}", name, value), None);
+        }
+    }
+
+    fn push_line(&mut self, line: &str, origin: Option<(usize, u32)>) {
+        self.preprocessed.source.push_str(line);
+        self.preprocessed.source.push('\n');
+        self.preprocessed.origins.push(origin);
+    }
+}
+
+/// The directive's name and the rest of the line, allowing whitespace around the `#`.
+fn parse_directive(line: &str) -> Option<(&str, &str)> {
+    let line = line.trim_start();
+    if !line.starts_with('#') {
+        return None;
+    }
+    let line = line[1..].trim_start();
+    let end = line
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .unwrap_or(line.len());
+    if end == 0 {
+        None
+    } else {
+        Some(line.split_at(end))
+    }
+}
+
+/// The rest of the line after `#name`, so `#include_next` isn't taken for `#include`.
+fn directive<'a>(line: &'a str, name: &str) -> Option<&'a str> {
+    match parse_directive(line) {
+        Some((found, rest)) if found == name => Some(rest),
+        _ => None,
+    }
+}
+
+/// The line with `//` and `/* */` comments taken out. `in_comment` carries a block comment
+/// from one line to the next.
+fn strip_comments(line: &str, in_comment: &mut bool) -> String {
+    let mut code = String::new();
+    let mut rest = line;
+    loop {
+        if *in_comment {
+            match rest.find("*/") {
+                Some(end) => {
+                    // A comment counts as whitespace, like in C.
+                    code.push(' ');
+                    rest = &rest[end + 2..];
+                    *in_comment = false;
+                }
+                None => return code,
+            }
+        } else {
+            match (rest.find("/*"), rest.find("//")) {
+                (Some(block), line_comment) if line_comment.is_none_or(|line_comment| block < line_comment) => {
+                    code.push_str(&rest[..block]);
+                    rest = &rest[block + 2..];
+                    *in_comment = true;
+                }
+                (_, Some(line_comment)) => {
+                    code.push_str(&rest[..line_comment]);
+                    return code;
+                }
+                _ => {
+                    code.push_str(rest);
+                    return code;
+                }
+            }
+        }
+    }
+}
+
+/// `"file"` or `<file>`, and whether it was quoted.
+fn parse_include(rest: &str) -> Option<(&str, bool)> {
+    let rest = rest.trim();
+    let (close, quoted) = if rest.starts_with('"') {
+        ('"', true)
+    } else if rest.starts_with('<') {
+        ('>', false)
+    } else {
+        return None;
+    };
+    let end = rest[1..].find(close)?;
+    let include = &rest[1..end + 1];
+    if include.is_empty() {
+        None
+    } else {
+        Some((include, quoted))
+    }
+}
+
+/// Falls back to the path as given, so a file that can't be canonicalized still gets a clear error later.
+fn canonical(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// `canonical` for a directory, where an empty path means the working directory.
+/// An empty path would otherwise be a prefix of everything.
+fn canonical_dir(dir: &Path) -> PathBuf {
+    if dir.as_os_str().is_empty() {
+        canonical(Path::new("."))
+    } else {
+        canonical(dir)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diagnostic::Diagnostic;
+    use std::env;
+
+    /// A fresh directory holding `files`, so tests don't see each other's includes.
+    fn shader_dir(test: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = env::temp_dir().join(format!("glsl-preprocess-{}-{}", test, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        for &(name, contents) in files {
+            let path = dir.join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+        dir
+    }
+
+    fn run(
+        dir: &Path,
+        source: &str,
+        defines: &[(&str, &str)],
+    ) -> Result<Preprocessed, IncludeError> {
+        let defines: Vec<(String, String)> = defines
+            .iter()
+            .map(|&(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        preprocess(&dir.join("shader.frag"), source, dir, &defines)
+    }
+
+    #[test]
+    fn defines_go_after_version() {
+        let dir = shader_dir("defines", &[]);
+        let preprocessed = run(
+            &dir,
+            "// header\n#version 450\nvoid main() {}\n",
+            &[("QUALITY", "2")],
+        )
+        .unwrap();
+        assert_eq!(
+            preprocessed.source,
+            "// header\n#version 450\n#define QUALITY 2\nvoid main() {}\n"
+        );
+        assert_eq!(
+            preprocessed.origin(2),
+            Some((dir.join("shader.frag").as_path(), 2))
+        );
+        assert_eq!(preprocessed.origin(3), None);
+        assert_eq!(
+            preprocessed.origin(4),
+            Some((dir.join("shader.frag").as_path(), 3))
+        );
+    }
+
+    #[test]
+    fn includes_are_expanded_and_traced() {
+        let dir = shader_dir(
+            "expand",
+            &[
+                (
+                    "lib/common.glsl",
+                    "#pragma once\n#include \"consts.glsl\"\nfloat helper();\n",
+                ),
+                ("lib/consts.glsl", "const float PI = 3.14;\n"),
+            ],
+        );
+        let source = "#version 450\n#include <lib/common.glsl>\n# include \"lib/common.glsl\"\nvoid main() {}\n";
+        let preprocessed = run(&dir, source, &[]).unwrap();
+        assert_eq!(
+            preprocessed.source,
+            "#version 450\nconst float PI = 3.14;\nfloat helper();\nvoid main() {}\n"
+        );
+        let common = canonical(&dir.join("lib/common.glsl"));
+        let consts = canonical(&dir.join("lib/consts.glsl"));
+        assert_eq!(preprocessed.includes, vec![common, consts]);
+        assert_eq!(
+            preprocessed.origin(2),
+            Some((dir.join("lib/consts.glsl").as_path(), 1))
+        );
+        assert_eq!(
+            preprocessed.origin(3),
+            Some((dir.join("lib/common.glsl").as_path(), 3))
+        );
+    }
+
+    #[test]
+    fn diagnostics_point_at_the_include() {
+        let dir = shader_dir("diagnostics", &[("broken.glsl", "// fine\nfloat x = y;\n")]);
+        let preprocessed = run(&dir, "#version 450\n#include \"broken.glsl\"\n", &[]).unwrap();
+        let diagnostics = Diagnostic::parse_preprocessed(
+            "shader.frag",
+            &preprocessed,
+            "ERROR: 0:3: 'y' : undeclared identifier\n",
+        );
+        assert_eq!(
+            diagnostics[0].file,
+            dir.join("broken.glsl").display().to_string()
+        );
+        assert_eq!(diagnostics[0].line, Some(2));
+        assert_eq!(diagnostics[0].column, Some(11));
+    }
+
+    #[test]
+    fn missing_and_malformed_includes_are_errors() {
+        let dir = shader_dir("missing", &[]);
+        match run(&dir, "#version 450\n#include \"nope.glsl\"\n", &[]) {
+            Err(IncludeError::NotFound {
+                line: 2,
+                ref include,
+                ..
+            }) if include == "nope.glsl" => (),
+            other => panic!("expected NotFound, got {:?}", other),
+        }
+        match run(&dir, "#include nope.glsl\n", &[]) {
+            Err(IncludeError::Malformed { line: 1, .. }) => (),
+            other => panic!("expected Malformed, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn includes_outside_the_root_are_errors() {
+        let base = shader_dir(
+            "outside",
+            &[
+                ("shaders/lib/a.glsl", "#include \"../b.glsl\"\n"),
+                ("shaders/b.glsl", ""),
+                ("secret.glsl", ""),
+            ],
+        );
+        let dir = base.join("shaders");
+        let preprocessed = run(&dir, "#include \"lib/a.glsl\"\n", &[]).unwrap();
+        assert_eq!(preprocessed.includes.len(), 2);
+        let secret = canonical(&base.join("secret.glsl"));
+        for source in &[
+            "#include \"../secret.glsl\"\n",
+            "#include <../secret.glsl>\n",
+        ] {
+            match run(&dir, source, &[]) {
+                Err(IncludeError::OutsideRoot {
+                    line: 1,
+                    ref resolved,
+                    ..
+                }) => assert_eq!(resolved, &secret),
+                other => panic!("expected OutsideRoot, got {:?}", other),
+            }
+        }
+        let absolute = format!("#include <{}>\n", secret.display());
+        match run(&dir, &absolute, &[]) {
+            Err(IncludeError::OutsideRoot { .. }) => (),
+            other => panic!("expected OutsideRoot, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn cycles_are_errors() {
+        let dir = shader_dir(
+            "cycle",
+            &[
+                ("a.glsl", "#include \"b.glsl\"\n"),
+                ("b.glsl", "#include \"a.glsl\"\n"),
+            ],
+        );
+        match run(&dir, "#include \"a.glsl\"\n", &[]) {
+            Err(IncludeError::Cycle { ref chain }) => assert_eq!(chain.len(), 4),
+            other => panic!("expected Cycle, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn includes_in_false_branches_are_skipped() {
+        let dir = shader_dir("branches", &[("texture.glsl", "uniform sampler2D tex;\n")]);
+        let source = "#version 450\n#ifdef USE_TEXTURE\n#include \"texture.glsl\"\n#include \"missing.glsl\"\n#endif\n";
+        let preprocessed = run(&dir, source, &[]).unwrap();
+        assert!(preprocessed.includes.is_empty());
+        assert!(!preprocessed.source.contains("include"));
+        match run(&dir, source, &[("USE_TEXTURE", "1")]) {
+            Err(IncludeError::NotFound { line: 4, .. }) => (),
+            other => panic!("expected NotFound, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn unresolvable_includes_in_unknown_branches_go_to_glslang() {
+        let dir = shader_dir("unknown", &[]);
+        let source = "#version 450\n#ifdef GL_EXT_foo\n#include \"ext.glsl\"\n#endif\n";
+        let preprocessed = run(&dir, source, &[]).unwrap();
+        assert_eq!(preprocessed.source, source);
+    }
+
+    #[test]
+    fn includes_in_comments_are_ignored() {
+        let dir = shader_dir("comments", &[]);
+        let source = "#version 450\n// #include \"a.glsl\"\n/*\n#include \"b.glsl\"\n*/\n";
+        let preprocessed = run(&dir, source, &[]).unwrap();
+        assert_eq!(preprocessed.source, source);
+    }
+
+    #[test]
+    fn directive_names_must_match_exactly() {
+        let dir = shader_dir("names", &[]);
+        let source = "#version 450\n#include_next \"a.glsl\"\n#versionx\n";
+        let preprocessed = run(&dir, source, &[("A", "1")]).unwrap();
+        assert_eq!(
+            preprocessed.source,
+            "#version 450\n#define A 1\n#include_next \"a.glsl\"\n#versionx\n"
+        );
+    }
+
+    #[test]
+    fn open_comments_survive_a_dropped_include() {
+        let dir = shader_dir("open-comment", &[("a.glsl", "float a;\n")]);
+        let source = "#include \"a.glsl\" /* note\n#include \"b.glsl\"\n*/\n";
+        let preprocessed = run(&dir, source, &[]).unwrap();
+        assert_eq!(
+            preprocessed.source,
+            "float a;\n/*\n#include \"b.glsl\"\n*/\n"
+        );
+    }
+}
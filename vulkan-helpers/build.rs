@@ -0,0 +1,47 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+//INFO (danny): The shader cache keys on the glslang that compiled each entry. `glsl-to-spirv` bundles its own,
+// so its exact version from `Cargo.lock` stands in for it, and a `cargo update` moves every key along.
+fn main() {
+    let mut searched = vec![];
+    for var in &["CARGO_MANIFEST_DIR", "OUT_DIR"] {
+        if let Some(dir) = env::var_os(var) {
+            searched.extend(PathBuf::from(dir).ancestors().map(Path::to_path_buf));
+        }
+    }
+    let version = searched
+        .iter()
+        .map(|dir| dir.join("Cargo.lock"))
+        .find(|lock| lock.is_file())
+        .and_then(|lock| {
+            println!("cargo:rerun-if-changed={}", lock.display());
+            fs::read_to_string(&lock).ok()
+        })
+        .and_then(|lock| locked_version(&lock, "glsl-to-spirv"));
+    let version = match version {
+        Some(version) => version,
+        None => {
+            println!("cargo:warning=could not find glsl-to-spirv in Cargo.lock, clear the shader cache after updating it");
+            String::from("unknown")
+        }
+    };
+    println!("cargo:rustc-env=GLSL_TO_SPIRV_VERSION={}", version);
+}
+
+/// The `version` under `name = "<name>"` in a `[[package]]` of the lock file.
+fn locked_version(lock: &str, name: &str) -> Option<String> {
+    let name_line = format!("name = \"{}\"", name);
+    let mut lines = lock.lines().map(str::trim);
+    while let Some(line) = lines.next() {
+        if line != name_line {
+            continue;
+        }
+        let version = lines.next()?;
+        if version.starts_with("version = \"") && version.ends_with('"') {
+            return Some(version["version = \"".len()..version.len() - 1].to_string());
+        }
+    }
+    None
+}
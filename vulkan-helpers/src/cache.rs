@@ -0,0 +1,271 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use vulkano_shaders::ShaderType;
+
+use reflect::spirv_words;
+
+/// Set this to put the shader cache somewhere other than the user's cache directory.
+pub const SHADER_CACHE_ENV_VAR: &str = "VKTUT_SHADER_CACHE";
+
+/// Part of every cache key, so SPIR-V from an older glslang is never reused.
+/// `build.rs` reads the `glsl-to-spirv` version out of `Cargo.lock`, which pins the glslang it bundles.
+pub const COMPILER_VERSION: &str = concat!("glsl-to-spirv ", env!("GLSL_TO_SPIRV_VERSION"));
+
+/// Entries that haven't been written for this many seconds, 30 days, are swept when a cache is opened,
+/// so shaders that were renamed or deleted don't stay around forever.
+pub const MAX_ENTRY_AGE_SECS: u64 = 30 * 24 * 60 * 60;
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// FNV-1a, because `DefaultHasher` isn't guaranteed to hash the same way between Rust releases.
+fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+    bytes
+        .iter()
+        .fold(hash, |hash, &byte| (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME))
+}
+
+/// Feeds the length first so `"ab" + "c"` and `"a" + "bc"` hash differently.
+fn hash_str(hash: u64, text: &str) -> u64 {
+    let len = text.len() as u64;
+    let len_bytes = [
+        len as u8,
+        (len >> 8) as u8,
+        (len >> 16) as u8,
+        (len >> 24) as u8,
+        (len >> 32) as u8,
+        (len >> 40) as u8,
+        (len >> 48) as u8,
+        (len >> 56) as u8,
+    ];
+    fnv1a(fnv1a(hash, &len_bytes), text.as_bytes())
+}
+
+/// Identifies one compile of one shader.
+///
+/// `variant` covers what makes two compiles of the same file different shaders, like defines.
+/// `hash` covers that plus everything the output depends on, like the source and its includes.
+/// An entry whose variant matches but whose hash doesn't is stale.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheKey {
+    name: String,
+    variant: u64,
+    hash: u64,
+}
+
+impl CacheKey {
+    pub fn new(path: &str, shader_type: &ShaderType) -> CacheKey {
+        let name: String = path
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        let variant = hash_str(FNV_OFFSET, &format!("{:?}", shader_type));
+        CacheKey {
+            name,
+            variant,
+            hash: hash_str(variant, COMPILER_VERSION),
+        }
+    }
+
+    /// Adds source text the SPIR-V depends on.
+    pub fn with_source(mut self, source: &str) -> CacheKey {
+        self.hash = hash_str(self.hash, source);
+        self
+    }
+
+    /// Adds something that picks out a different variant of the same shader.
+    pub fn with_variant(mut self, variant: &str) -> CacheKey {
+        self.variant = hash_str(self.variant, variant);
+        self.hash = hash_str(self.hash, variant);
+        self
+    }
+
+    fn prefix(&self) -> String {
+        format!("{}-{:016x}-", self.name, self.variant)
+    }
+
+    fn file_name(&self) -> String {
+        format!("{}{:016x}.spv", self.prefix(), self.hash)
+    }
+}
+
+/// Compiled SPIR-V on disk, so unchanged shaders skip glslang on the next launch.
+#[derive(Debug, Clone)]
+pub struct ShaderCache {
+    dir: PathBuf,
+}
+
+impl ShaderCache {
+    /// Opens the cache in `dir`, creating it if needed, and sweeps entries older than `MAX_ENTRY_AGE_SECS`.
+    ///
+    /// Anything in here ends up in the driver, so a directory someone else owns or could write to is refused.
+    pub fn new<P: Into<PathBuf>>(dir: P) -> io::Result<ShaderCache> {
+        let dir = dir.into();
+        create_private_dir(&dir)?;
+        check_private_dir(&dir)?;
+        let cache = ShaderCache { dir };
+        // A sweep that fails only leaves some old files around until the next one.
+        let _ = cache.sweep(Duration::from_secs(MAX_ENTRY_AGE_SECS));
+        Ok(cache)
+    }
+
+    /// `VKTUT_SHADER_CACHE` if it's set, otherwise `vktut-shader-cache` in the user's cache directory:
+    /// `$XDG_CACHE_HOME` or `~/.cache` on Linux, `~/Library/Caches` on macOS and `%LOCALAPPDATA%` on Windows.
+    /// `None` when there's no home directory to put it in.
+    pub fn default_dir() -> Option<PathBuf> {
+        if let Some(dir) = env::var_os(SHADER_CACHE_ENV_VAR) {
+            return Some(PathBuf::from(dir));
+        }
+        user_cache_dir().map(|dir| dir.join("vktut-shader-cache"))
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The cached SPIR-V for `key`. Entries that don't look like SPIR-V count as misses.
+    pub fn get(&self, key: &CacheKey) -> Option<Vec<u8>> {
+        let bytes = fs::read(self.dir.join(key.file_name())).ok()?;
+        match spirv_words(&bytes) {
+            Ok(_) => Some(bytes),
+            Err(_) => None,
+        }
+    }
+
+    /// Stores SPIR-V for `key` and prunes older entries for the same variant.
+    pub fn put(&self, key: &CacheKey, spirv_bytes: &[u8]) -> io::Result<()> {
+        let file_name = key.file_name();
+        // NOTE: Write then rename, so a crash halfway through never leaves a truncated entry behind.
+        let temp_path = self.dir.join(format!("{}.tmp", file_name));
+        fs::write(&temp_path, spirv_bytes)?;
+        fs::rename(&temp_path, self.dir.join(&file_name))?;
+        self.prune(key)
+    }
+
+    fn prune(&self, key: &CacheKey) -> io::Result<()> {
+        let prefix = key.prefix();
+        let file_name = key.file_name();
+        for entry in fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            if name.starts_with(&prefix) && name != file_name {
+                fs::remove_file(entry.path())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes entries and leftover temp files that haven't been written for `max_age`.
+    /// Returns how many were removed.
+    pub fn sweep(&self, max_age: Duration) -> io::Result<usize> {
+        let now = SystemTime::now();
+        let mut removed = 0;
+        for entry in fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+            if !metadata.is_file() {
+                continue;
+            }
+            let age = metadata
+                .modified()
+                .ok()
+                .and_then(|modified| now.duration_since(modified).ok());
+            if age.map_or(false, |age| age > max_age) {
+                fs::remove_file(entry.path())?;
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+
+    /// Removes every entry.
+    pub fn clear(&self) -> io::Result<()> {
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.is_file() {
+                fs::remove_file(path)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn user_cache_dir() -> Option<PathBuf> {
+    env::var_os("LOCALAPPDATA").map(PathBuf::from)
+}
+
+#[cfg(target_os = "macos")]
+fn user_cache_dir() -> Option<PathBuf> {
+    env::var_os("HOME").map(|home| PathBuf::from(home).join("Library").join("Caches"))
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn user_cache_dir() -> Option<PathBuf> {
+    match env::var_os("XDG_CACHE_HOME") {
+        Some(ref dir) if Path::new(dir).is_absolute() => Some(PathBuf::from(dir)),
+        _ => env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")),
+    }
+}
+
+#[cfg(unix)]
+fn create_private_dir(dir: &Path) -> io::Result<()> {
+    use std::os::unix::fs::DirBuilderExt;
+    fs::DirBuilder::new().recursive(true).mode(0o700).create(dir)
+}
+
+#[cfg(not(unix))]
+fn create_private_dir(dir: &Path) -> io::Result<()> {
+    fs::create_dir_all(dir)
+}
+
+/// Refuses a directory other users can write to, then makes sure we own it by creating a file in it
+/// and comparing owners. `create_new` won't follow a symlink someone planted under that name,
+/// and the probe is named after our pid so processes opening the cache together don't collide.
+#[cfg(unix)]
+fn check_private_dir(dir: &Path) -> io::Result<()> {
+    use std::os::unix::fs::MetadataExt;
+    let refuse = |reason: &str| {
+        Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!("{} {}", dir.display(), reason),
+        ))
+    };
+    let metadata = fs::symlink_metadata(dir)?;
+    if !metadata.is_dir() {
+        return refuse("is not a directory");
+    }
+    if metadata.mode() & 0o022 != 0 {
+        return refuse("can be written to by other users");
+    }
+    // A leftover from a crashed process that had our pid is ours to remove, nobody else's is touched.
+    let probe = dir.join(format!(".owner-check-{}", std::process::id()));
+    let _ = fs::remove_file(&probe);
+    fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&probe)?;
+    let probe_uid = fs::symlink_metadata(&probe).map(|probe| probe.uid());
+    fs::remove_file(&probe)?;
+    if probe_uid? != metadata.uid() {
+        return refuse("belongs to another user");
+    }
+    Ok(())
+}
+
+/// `%LOCALAPPDATA%` is only readable by its user already.
+#[cfg(not(unix))]
+fn check_private_dir(dir: &Path) -> io::Result<()> {
+    if fs::metadata(dir)?.is_dir() {
+        Ok(())
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} is not a directory", dir.display()),
+        ))
+    }
+}
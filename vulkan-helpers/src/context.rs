@@ -0,0 +1,447 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use vulkano::device::{Device, DeviceExtensions, Queue};
+use vulkano::framebuffer::{FramebufferAbstract, RenderPassAbstract};
+use vulkano::image::SwapchainImage;
+use vulkano::instance::{Features, PhysicalDevice};
+use vulkano::pipeline::GraphicsPipelineAbstract;
+use vulkano::swapchain::{PresentMode, Surface, Swapchain, SwapchainCreationError};
+use vulkano::sync::SharingMode;
+use winit;
+
+use cache::ShaderCache;
+use device::init_device;
+use error::VulkanInitError;
+use instance::{init_vulkan_debug_callbacks, init_vulkan_instance, init_vulkan_layers};
+use pipeline::{create_pipeline, create_shader_stages, PipelineDesc, ShaderStages,
+               Specialization};
+use queues::{swapchain_sharing_mode, QueueFamilyIndices};
+use render_pass::{create_framebuffers, create_render_pass};
+use requirements::DeviceRequirements;
+use shader::create_shader;
+use vulkano_shaders::ShaderType;
+use selection::{DeviceSelector, RankedDevice};
+use surface::{framebuffer_dimensions, init_surface, init_window};
+use swapchain::{init_swapchain, ImageCountPolicy, PresentModePolicy, SurfaceFormat, SwapchainConfig,
+                SwapchainState};
+
+pub struct VulkanContext {
+    pub surface: Arc<Surface<winit::Window>>,
+    pub device: Arc<Device>,
+    pub ranked_device: RankedDevice,
+    pub queue_families: QueueFamilyIndices,
+    pub graphics_queue: Arc<Queue>,
+    pub present_queue: Arc<Queue>,
+    pub transfer_queue: Option<Arc<Queue>>,
+    pub compute_queue: Option<Arc<Queue>>,
+    pub swapchain: Arc<Swapchain<winit::Window>>,
+    pub swapchain_config: SwapchainConfig,
+    pub surface_format: SurfaceFormat,
+    pub present_mode: PresentMode,
+    pub images: Vec<Arc<SwapchainImage<winit::Window>>>,
+    /// Set when the window resized or presenting reported the swapchain out of date.
+    pub swapchain_outdated: bool,
+    pub render_pass: Arc<RenderPassAbstract + Send + Sync>,
+    /// One per swapchain image, indexed the same way.
+    pub framebuffers: Vec<Arc<FramebufferAbstract + Send + Sync>>,
+    /// Only there when the builder was given a `PipelineDesc`.
+    pub shader_stages: Option<ShaderStages>,
+    pub pipeline: Option<Arc<GraphicsPipelineAbstract + Send + Sync>>,
+    /// Where compiled GLSL is kept between launches, `None` when it's turned off or couldn't be created.
+    pub shader_cache: Option<ShaderCache>,
+}
+
+impl VulkanContext {
+    pub fn physical_device(&self) -> PhysicalDevice {
+        self.device.physical_device()
+    }
+
+    /// Required features plus the optional ones the device had.
+    pub fn enabled_features(&self) -> &Features {
+        self.device.enabled_features()
+    }
+
+    /// Required extensions plus the optional ones the device had.
+    pub fn enabled_extensions(&self) -> &DeviceExtensions {
+        self.device.loaded_extensions()
+    }
+
+    /// The dedicated transfer queue, or the graphics queue when there isn't one.
+    pub fn transfer_or_graphics_queue(&self) -> &Arc<Queue> {
+        self.transfer_queue.as_ref().unwrap_or(&self.graphics_queue)
+    }
+
+    /// The async compute queue, or the graphics queue when there isn't one.
+    pub fn compute_or_graphics_queue(&self) -> &Arc<Queue> {
+        self.compute_queue.as_ref().unwrap_or(&self.graphics_queue)
+    }
+
+    pub fn swapchain_sharing_mode(&self) -> SharingMode {
+        swapchain_sharing_mode(&self.graphics_queue, &self.present_queue)
+    }
+
+    /// Switches present modes by rebuilding the swapchain.
+    /// Anything made from the old swapchain images has to be rebuilt too.
+    pub fn set_present_mode_policy(
+        &mut self,
+        policy: PresentModePolicy,
+    ) -> Result<bool, VulkanInitError> {
+        self.swapchain_config.present_modes = policy.present_modes();
+        self.swapchain_outdated = true;
+        self.recreate_swapchain()
+    }
+
+    /// How many images the swapchain actually has, which can be more than asked for.
+    /// Size anything kept per swapchain image by this.
+    pub fn image_count(&self) -> usize {
+        self.images.len()
+    }
+
+    /// Call on `WindowEvent::Resized`, or when acquire or present returns `OutOfDate`.
+    pub fn invalidate_swapchain(&mut self) {
+        self.swapchain_outdated = true;
+    }
+
+    /// A minimized window has nothing to render into.
+    pub fn is_minimized(&self) -> bool {
+        match framebuffer_dimensions(self.surface.window()) {
+            Some([width, height]) => width == 0 || height == 0,
+            None => true,
+        }
+    }
+
+    /// Rebuilds the swapchain, its images and everything made from them if it was invalidated.
+    ///
+    /// Returns `false` while it still can't be rebuilt, like when the window is minimized,
+    /// in which case rendering should pause and this be tried again later.
+    pub fn recreate_swapchain(&mut self) -> Result<bool, VulkanInitError> {
+        if !self.swapchain_outdated {
+            return Ok(true);
+        }
+        if self.is_minimized() {
+            return Ok(false);
+        }
+        let state = match init_swapchain(
+            self.device.clone(),
+            self.surface.clone(),
+            self.swapchain_sharing_mode(),
+            &self.swapchain_config,
+            Some(&self.swapchain),
+        ) {
+            Ok(state) => state,
+            // NOTE: The surface can change size again between querying it and building the swapchain.
+            Err(VulkanInitError::Swapchain(SwapchainCreationError::UnsupportedDimensions)) => {
+                return Ok(false)
+            }
+            Err(err) => return Err(err),
+        };
+        self.set_swapchain_state(state);
+        self.render_pass = create_render_pass(self.device.clone(), self.surface_format.0)?;
+        self.framebuffers = create_framebuffers(self.render_pass.clone(), &self.images)?;
+        self.pipeline = match self.shader_stages {
+            Some(ref stages) => Some(create_pipeline(
+                self.device.clone(),
+                stages,
+                self.render_pass.clone(),
+                self.swapchain.dimensions(),
+            )?),
+            None => None,
+        };
+        self.swapchain_outdated = false;
+        Ok(true)
+    }
+
+    /// Recompiles whichever stages were loaded from a file with the same name as `path`, or include it,
+    /// then rebuilds the pipeline around them.
+    ///
+    /// Returns `false` when `path` has nothing to do with the pipeline's shaders.
+    /// On errors the old shaders and pipeline are kept, so a typo doesn't take the window down.
+    pub fn reload_shader(&mut self, path: &Path) -> Result<bool, VulkanInitError> {
+        let mut stages = match self.shader_stages {
+            Some(ref stages) => stages.clone(),
+            None => return Ok(false),
+        };
+        let reloads = stages.reloads_for(path);
+        if reloads.is_empty() {
+            return Ok(false);
+        }
+        for (shader_type, source_path) in reloads {
+            let shader = create_shader(
+                self.device.clone(),
+                &source_path.to_string_lossy(),
+                shader_type.clone(),
+                &stages.compile_options,
+                self.shader_cache.as_ref(),
+            )?;
+            match shader_type {
+                ShaderType::Vertex => stages.vertex = shader,
+                _ => stages.fragment = shader,
+            }
+        }
+        let pipeline = create_pipeline(
+            self.device.clone(),
+            &stages,
+            self.render_pass.clone(),
+            self.swapchain.dimensions(),
+        )?;
+        self.shader_stages = Some(stages);
+        self.pipeline = Some(pipeline);
+        Ok(true)
+    }
+
+    /// Rebuilds the pipeline with new specialization constants, without recompiling any shaders.
+    ///
+    /// Returns `false` when there's no pipeline. On errors the old pipeline is kept.
+    pub fn set_specialization(
+        &mut self,
+        specialization: Option<Specialization>,
+    ) -> Result<bool, VulkanInitError> {
+        let mut stages = match self.shader_stages {
+            Some(ref stages) => stages.clone(),
+            None => return Ok(false),
+        };
+        stages.specialization = specialization;
+        let pipeline = create_pipeline(
+            self.device.clone(),
+            &stages,
+            self.render_pass.clone(),
+            self.swapchain.dimensions(),
+        )?;
+        self.shader_stages = Some(stages);
+        self.pipeline = Some(pipeline);
+        Ok(true)
+    }
+
+    fn set_swapchain_state(&mut self, state: SwapchainState) {
+        self.swapchain = state.swapchain;
+        self.images = state.images;
+        self.surface_format = state.surface_format;
+        self.present_mode = state.present_mode;
+    }
+}
+
+pub fn init_vulkan(
+    events_loop: &winit::EventsLoop,
+) -> Result<Box<VulkanContext>, VulkanInitError> {
+    VulkanContextBuilder::new().build(events_loop)
+}
+
+/// Configures everything `init_vulkan` would otherwise pick for you.
+pub struct VulkanContextBuilder {
+    dimensions: [u32; 2],
+    title: String,
+    swapchain: SwapchainConfig,
+    requirements: DeviceRequirements,
+    layers: Option<Vec<String>>,
+    device_selector: Option<DeviceSelector>,
+    pipeline: Option<PipelineDesc>,
+    shader_cache: Option<PathBuf>,
+}
+
+impl Default for VulkanContextBuilder {
+    fn default() -> VulkanContextBuilder {
+        VulkanContextBuilder::new()
+    }
+}
+
+impl VulkanContextBuilder {
+    /// The device selector starts out as whatever `--device` or `VKTUT_DEVICE` asked for,
+    /// and the shader cache as `ShaderCache::default_dir()`.
+    pub fn new() -> VulkanContextBuilder {
+        VulkanContextBuilder {
+            dimensions: [800, 600],
+            title: String::from("Vulkan"),
+            swapchain: SwapchainConfig::default(),
+            requirements: DeviceRequirements::default(),
+            layers: None,
+            device_selector: DeviceSelector::from_env_or_args(),
+            pipeline: None,
+            shader_cache: ShaderCache::default_dir(),
+        }
+    }
+
+    pub fn with_dimensions(mut self, width: u32, height: u32) -> VulkanContextBuilder {
+        self.dimensions = [width, height];
+        self
+    }
+
+    pub fn with_title<T: Into<String>>(mut self, title: T) -> VulkanContextBuilder {
+        self.title = title.into();
+        self
+    }
+
+    /// Defaults to `PresentModePolicy::Vsync`.
+    pub fn with_present_mode_policy(mut self, policy: PresentModePolicy) -> VulkanContextBuilder {
+        self.swapchain.present_modes = policy.present_modes();
+        self
+    }
+
+    pub fn with_vsync(self, vsync: bool) -> VulkanContextBuilder {
+        self.with_present_mode_policy(PresentModePolicy::from_vsync(vsync))
+    }
+
+    /// Present modes to try in order, falling back to `Fifo`.
+    pub fn with_present_modes(mut self, present_modes: Vec<PresentMode>) -> VulkanContextBuilder {
+        self.swapchain.present_modes = present_modes;
+        self
+    }
+
+    /// Formats to try in order, replacing the default `B8G8R8A8Srgb` preference.
+    /// Falls back to any `SrgbNonLinear` format, then to the first one the surface supports.
+    pub fn with_formats(mut self, formats: Vec<SurfaceFormat>) -> VulkanContextBuilder {
+        self.swapchain.formats = formats;
+        self
+    }
+
+    /// Defaults to `ImageCountPolicy::MinPlusOne`, clamped to what the surface supports.
+    pub fn with_image_count_policy(mut self, policy: ImageCountPolicy) -> VulkanContextBuilder {
+        self.swapchain.image_count = policy;
+        self
+    }
+
+    pub fn with_image_count(self, image_count: u32) -> VulkanContextBuilder {
+        self.with_image_count_policy(ImageCountPolicy::Exact(image_count))
+    }
+
+    /// Devices without these are rejected.
+    pub fn with_required_device_features(mut self, features: Features) -> VulkanContextBuilder {
+        self.requirements.required_features = features;
+        self
+    }
+
+    /// Enabled when supported, and devices supporting them rank higher.
+    pub fn with_optional_device_features(mut self, features: Features) -> VulkanContextBuilder {
+        self.requirements.optional_features = features;
+        self
+    }
+
+    /// Devices without these are rejected.
+    pub fn with_required_device_extensions(
+        mut self,
+        extensions: DeviceExtensions,
+    ) -> VulkanContextBuilder {
+        self.requirements.required_extensions = extensions;
+        self
+    }
+
+    /// Enabled when supported.
+    pub fn with_optional_device_extensions(
+        mut self,
+        extensions: DeviceExtensions,
+    ) -> VulkanContextBuilder {
+        self.requirements.optional_extensions = extensions;
+        self
+    }
+
+    /// Replaces the layers `vk_debug` would pick.
+    pub fn with_layers(mut self, layers: Vec<String>) -> VulkanContextBuilder {
+        self.layers = Some(layers);
+        self
+    }
+
+    /// Overrides device ranking, `None` lets the highest score win.
+    pub fn with_device_selector(mut self, selector: Option<DeviceSelector>) -> VulkanContextBuilder {
+        self.device_selector = selector;
+        self
+    }
+
+    /// Compiles these shaders and builds a graphics pipeline around them.
+    pub fn with_pipeline(mut self, pipeline: PipelineDesc) -> VulkanContextBuilder {
+        self.pipeline = Some(pipeline);
+        self
+    }
+
+    /// Keeps compiled GLSL in `dir` so unchanged shaders aren't recompiled on the next launch.
+    pub fn with_shader_cache<P: Into<PathBuf>>(mut self, dir: P) -> VulkanContextBuilder {
+        self.shader_cache = Some(dir.into());
+        self
+    }
+
+    /// Always compiles GLSL from scratch.
+    pub fn without_shader_cache(mut self) -> VulkanContextBuilder {
+        self.shader_cache = None;
+        self
+    }
+
+    pub fn build(
+        self,
+        events_loop: &winit::EventsLoop,
+    ) -> Result<Box<VulkanContext>, VulkanInitError> {
+        let layers = match self.layers {
+            Some(layers) => layers,
+            None => init_vulkan_layers()?,
+        };
+        let instance = init_vulkan_instance(&layers)?;
+        init_vulkan_debug_callbacks(instance.clone());
+        let window = init_window(self.dimensions, &self.title);
+        let surface = init_surface(events_loop, instance.clone(), window)?;
+        let (device, queues, queue_families, ranked_device) = init_device(
+            instance.clone(),
+            surface.clone(),
+            &self.requirements,
+            self.device_selector.as_ref(),
+        )?;
+        let sharing = swapchain_sharing_mode(&queues.graphics, &queues.present);
+        let state = init_swapchain(
+            device.clone(),
+            surface.clone(),
+            sharing,
+            &self.swapchain,
+            None,
+        )?;
+        let render_pass = create_render_pass(device.clone(), state.surface_format.0)?;
+        let framebuffers = create_framebuffers(render_pass.clone(), &state.images)?;
+        let shader_cache = match self.shader_cache {
+            Some(ref dir) => match ShaderCache::new(dir.clone()) {
+                Ok(cache) => Some(cache),
+                Err(err) => {
+                    println!(
+                        "Shader cache disabled, could not create {}: {}",
+                        dir.display(),
+                        err
+                    );
+                    None
+                }
+            },
+            None => None,
+        };
+        let shader_stages = match self.pipeline {
+            Some(ref desc) => Some(create_shader_stages(
+                device.clone(),
+                desc,
+                shader_cache.as_ref(),
+            )?),
+            None => None,
+        };
+        let pipeline = match shader_stages {
+            Some(ref stages) => Some(create_pipeline(
+                device.clone(),
+                stages,
+                render_pass.clone(),
+                state.swapchain.dimensions(),
+            )?),
+            None => None,
+        };
+        Ok(Box::new(VulkanContext {
+            device,
+            ranked_device,
+            queue_families,
+            graphics_queue: queues.graphics,
+            present_queue: queues.present,
+            transfer_queue: queues.transfer,
+            compute_queue: queues.compute,
+            surface,
+            swapchain: state.swapchain,
+            swapchain_config: self.swapchain,
+            surface_format: state.surface_format,
+            present_mode: state.present_mode,
+            images: state.images,
+            swapchain_outdated: false,
+            render_pass,
+            framebuffers,
+            shader_stages,
+            pipeline,
+            shader_cache,
+        }))
+    }
+}
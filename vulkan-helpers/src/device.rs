@@ -0,0 +1,118 @@
+use std::sync::Arc;
+use vulkano::device::{Device, DeviceExtensions};
+use vulkano::instance::{Instance, PhysicalDevice};
+use vulkano::swapchain::Surface;
+use winit;
+
+use error::VulkanInitError;
+use queues::{find_queue_families, pick_queue_families, take_queues, QueueFamilyIndices, Queues};
+use requirements::DeviceRequirements;
+use selection::{rank_devices, DeviceSelector, RankedDevice};
+
+pub fn init_device(
+    instance: Arc<Instance>,
+    surface: Arc<Surface<winit::Window>>,
+    requirements: &DeviceRequirements,
+    selector: Option<&DeviceSelector>,
+) -> Result<(Arc<Device>, Queues, QueueFamilyIndices, RankedDevice), VulkanInitError> {
+    println!("Picking PhysicalDevice");
+    let mut ranked = rank_devices(&instance, &surface, requirements);
+    if let Some(selector) = selector {
+        println!("Restricting PhysicalDevices to {}", selector);
+        if !ranked.iter().any(|ranked_device| selector.matches(ranked_device)) {
+            return Err(VulkanInitError::DeviceOverrideNotFound {
+                selector: selector.clone(),
+                available: ranked,
+            });
+        }
+        ranked.retain(|ranked_device| selector.matches(ranked_device));
+    }
+    let ranked_device = match ranked.iter().position(|ranked_device| ranked_device.suitable()) {
+        Some(position) => ranked.swap_remove(position),
+        None => return Err(VulkanInitError::NoSuitablePhysicalDevice { rejected: ranked }),
+    };
+    let physical_device = PhysicalDevice::from_index(&instance, ranked_device.index)
+        .ok_or(VulkanInitError::NoSuitablePhysicalDevice { rejected: vec![] })?;
+    println!("Picked {}", ranked_device.name);
+    let features = requirements.enabled_features(physical_device);
+    let extensions = requirements.enabled_extensions(physical_device);
+    println!("  enabled extensions: {:?}", extensions);
+    println!("Picking Queue Families");
+    let indices = find_queue_families(physical_device, &surface)?;
+    let queue_families = indices
+        .unique()
+        .into_iter()
+        .filter_map(|id| physical_device.queue_family_by_id(id))
+        .map(|queue_family| (queue_family, 1.0));
+    let (device, queues_iter) =
+        Device::new(physical_device, &features, &extensions, queue_families)?;
+    let queues = take_queues(&indices, queues_iter)?;
+    Ok((device, queues, indices, ranked_device))
+}
+
+/// Prints what the device supports and returns why it was rejected, if it was.
+pub fn check_device_support(
+    physical_device: PhysicalDevice,
+    surface: &Surface<winit::Window>,
+    requirements: &DeviceRequirements,
+) -> Option<String> {
+    let rejection = match (
+        requirements.rejection(physical_device),
+        surface_rejection(physical_device, surface),
+    ) {
+        (Some(missing), Some(surface_reasons)) => Some(format!("{}, {}", missing, surface_reasons)),
+        (missing, surface_reasons) => missing.or(surface_reasons),
+    };
+    match rejection {
+        None => print!("  ✔️ "),
+        Some(_) => print!("  ❌ "),
+    }
+    println!(
+        "{}, type: {:?}\n  supports: {}, driver: {}",
+        physical_device.name(),
+        physical_device.ty(),
+        physical_device.api_version(),
+        physical_device.driver_version(),
+    );
+    if let Some(ref reason) = rejection {
+        println!("  rejected: {}", reason);
+    }
+    let extensions = requirements
+        .required_extensions
+        .union(&requirements.optional_extensions);
+    println!("  device extensions:");
+    let supported = DeviceExtensions::supported_by_device(physical_device);
+    print!("    ✔️ ");
+    println!("{:?}", supported.intersection(&extensions));
+    print!("    ❌ ");
+    println!("{:?}", extensions.difference(&supported));
+    rejection
+}
+
+/// Why `physical_device` can't draw to `surface`, like the tutorial's `isDeviceSuitable`:
+/// it needs graphics and present queue families, plus at least one surface format and present mode.
+pub fn surface_rejection(
+    physical_device: PhysicalDevice,
+    surface: &Surface<winit::Window>,
+) -> Option<String> {
+    let mut reasons = vec![];
+    if pick_queue_families(physical_device, surface).is_none() {
+        reasons.push(String::from("no graphics and present queue families"));
+    }
+    match surface.capabilities(physical_device) {
+        Ok(caps) => {
+            if caps.supported_formats.is_empty() {
+                reasons.push(String::from("no surface formats"));
+            }
+            if caps.present_modes.iter().next().is_none() {
+                reasons.push(String::from("no present modes"));
+            }
+        }
+        Err(err) => reasons.push(format!("could not query surface capabilities: {}", err)),
+    }
+    if reasons.is_empty() {
+        None
+    } else {
+        Some(reasons.join(", "))
+    }
+}
@@ -0,0 +1,239 @@
+use std::error::Error;
+use std::fmt;
+use vulkano::device::DeviceCreationError;
+use vulkano::framebuffer::{FramebufferCreationError, RenderPassCreationError};
+use vulkano::instance::{InstanceCreationError, LayersListError};
+use vulkano::pipeline::GraphicsPipelineCreationError;
+use vulkano::swapchain::{CapabilitiesError, SwapchainCreationError};
+use vulkano_win::CreationError;
+
+use reflect::{DescriptorBinding, InterfaceMismatch, PushConstantRange, SpecializationMismatch};
+use selection::{DeviceSelector, RankedDevice};
+use shader_error::ShaderError;
+
+/// Everything that can go wrong while bringing up a `VulkanContext`.
+#[derive(Debug)]
+pub enum VulkanInitError {
+    Layers(LayersListError),
+    Instance(InstanceCreationError),
+    Surface(CreationError),
+    NoSuitablePhysicalDevice {
+        rejected: Vec<RankedDevice>,
+    },
+    DeviceOverrideNotFound {
+        selector: DeviceSelector,
+        available: Vec<RankedDevice>,
+    },
+    NoSuitableQueueFamily,
+    Device(DeviceCreationError),
+    SurfaceCapabilities(CapabilitiesError),
+    NoSupportedFormat,
+    NoSupportedCompositeAlpha,
+    Swapchain(SwapchainCreationError),
+    Shader(ShaderError),
+    RenderPass(RenderPassCreationError),
+    NoSubpass,
+    Framebuffer(FramebufferCreationError),
+    /// The fragment shader reads varyings the vertex shader doesn't write, or writes with another type.
+    InterfaceMismatch {
+        vertex: String,
+        fragment: String,
+        mismatches: Vec<InterfaceMismatch>,
+    },
+    /// A specialization constants struct doesn't line up with the constants its shader declares.
+    Specialization {
+        shader: String,
+        mismatches: Vec<SpecializationMismatch>,
+    },
+    /// A shader uses descriptors or push constants, which the pipeline layout has no room for yet.
+    PipelineLayout {
+        shader: String,
+        descriptor_bindings: Vec<DescriptorBinding>,
+        push_constant_ranges: Vec<PushConstantRange>,
+    },
+    Pipeline(GraphicsPipelineCreationError),
+}
+
+impl fmt::Display for VulkanInitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            VulkanInitError::Layers(ref err) => write!(f, "failed to list layers: {}", err),
+            VulkanInitError::Instance(ref err) => {
+                write!(f, "failed to create Vulkan instance: {}", err)
+            }
+            VulkanInitError::Surface(ref err) => write!(f, "failed to create surface: {}", err),
+            VulkanInitError::NoSuitablePhysicalDevice { ref rejected } => {
+                write!(f, "no suitable physical device found")?;
+                for device in rejected {
+                    write!(f, "\n  {}", device)?;
+                    if let Some(ref reason) = device.rejection {
+                        write!(f, ": {}", reason)?;
+                    }
+                }
+                Ok(())
+            }
+            VulkanInitError::DeviceOverrideNotFound {
+                ref selector,
+                ref available,
+            } => {
+                write!(f, "no physical device matches {}, available devices:", selector)?;
+                for device in available {
+                    write!(f, "\n  {}", device)?;
+                }
+                Ok(())
+            }
+            VulkanInitError::NoSuitableQueueFamily => write!(f, "no suitable queue family found"),
+            VulkanInitError::Device(ref err) => write!(f, "couldn't build device: {}", err),
+            VulkanInitError::SurfaceCapabilities(ref err) => {
+                write!(f, "failed to get surface capabilities: {}", err)
+            }
+            VulkanInitError::NoSupportedFormat => write!(f, "surface supports no formats"),
+            VulkanInitError::NoSupportedCompositeAlpha => {
+                write!(f, "surface supports no composite alpha modes")
+            }
+            VulkanInitError::Swapchain(ref err) => write!(f, "failed to create swapchain: {}", err),
+            VulkanInitError::Shader(ref err) => write!(f, "{}", err),
+            VulkanInitError::RenderPass(ref err) => {
+                write!(f, "failed to create render pass: {}", err)
+            }
+            VulkanInitError::NoSubpass => write!(f, "render pass has no subpass"),
+            VulkanInitError::Framebuffer(ref err) => {
+                write!(f, "failed to create framebuffer: {}", err)
+            }
+            VulkanInitError::InterfaceMismatch {
+                ref vertex,
+                ref fragment,
+                ref mismatches,
+            } => {
+                write!(f, "{} and {} don't agree on their interface", vertex, fragment)?;
+                for mismatch in mismatches {
+                    write!(f, "\n  {}", mismatch)?;
+                }
+                Ok(())
+            }
+            VulkanInitError::Specialization {
+                ref shader,
+                ref mismatches,
+            } => {
+                write!(f, "specialization constants don't match {}", shader)?;
+                for mismatch in mismatches {
+                    write!(f, "\n  {}", mismatch)?;
+                }
+                Ok(())
+            }
+            VulkanInitError::PipelineLayout {
+                ref shader,
+                ref descriptor_bindings,
+                ref push_constant_ranges,
+            } => {
+                write!(
+                    f,
+                    "{} needs a pipeline layout, but pipelines are only built with an empty one",
+                    shader
+                )?;
+                for binding in descriptor_bindings {
+                    write!(
+                        f,
+                        "\n  set {}, binding {}: {:?}",
+                        binding.set, binding.binding, binding.kind
+                    )?;
+                    if let Some(ref name) = binding.name {
+                        write!(f, " {}", name)?;
+                    }
+                }
+                for range in push_constant_ranges {
+                    write!(
+                        f,
+                        "\n  push constants at offset {}, {} bytes",
+                        range.offset, range.size
+                    )?;
+                    if let Some(ref name) = range.name {
+                        write!(f, " {}", name)?;
+                    }
+                }
+                Ok(())
+            }
+            VulkanInitError::Pipeline(ref err) => {
+                write!(f, "failed to create graphics pipeline: {}", err)
+            }
+        }
+    }
+}
+
+impl Error for VulkanInitError {
+    fn source(&self) -> Option<&(Error + 'static)> {
+        match *self {
+            VulkanInitError::Layers(ref err) => Some(err),
+            VulkanInitError::Instance(ref err) => Some(err),
+            VulkanInitError::Surface(ref err) => Some(err),
+            VulkanInitError::Device(ref err) => Some(err),
+            VulkanInitError::SurfaceCapabilities(ref err) => Some(err),
+            VulkanInitError::Swapchain(ref err) => Some(err),
+            VulkanInitError::Shader(ref err) => Some(err),
+            VulkanInitError::RenderPass(ref err) => Some(err),
+            VulkanInitError::Framebuffer(ref err) => Some(err),
+            VulkanInitError::Pipeline(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<LayersListError> for VulkanInitError {
+    fn from(err: LayersListError) -> VulkanInitError {
+        VulkanInitError::Layers(err)
+    }
+}
+
+impl From<InstanceCreationError> for VulkanInitError {
+    fn from(err: InstanceCreationError) -> VulkanInitError {
+        VulkanInitError::Instance(err)
+    }
+}
+
+impl From<CreationError> for VulkanInitError {
+    fn from(err: CreationError) -> VulkanInitError {
+        VulkanInitError::Surface(err)
+    }
+}
+
+impl From<DeviceCreationError> for VulkanInitError {
+    fn from(err: DeviceCreationError) -> VulkanInitError {
+        VulkanInitError::Device(err)
+    }
+}
+
+impl From<CapabilitiesError> for VulkanInitError {
+    fn from(err: CapabilitiesError) -> VulkanInitError {
+        VulkanInitError::SurfaceCapabilities(err)
+    }
+}
+
+impl From<SwapchainCreationError> for VulkanInitError {
+    fn from(err: SwapchainCreationError) -> VulkanInitError {
+        VulkanInitError::Swapchain(err)
+    }
+}
+
+impl From<RenderPassCreationError> for VulkanInitError {
+    fn from(err: RenderPassCreationError) -> VulkanInitError {
+        VulkanInitError::RenderPass(err)
+    }
+}
+
+impl From<GraphicsPipelineCreationError> for VulkanInitError {
+    fn from(err: GraphicsPipelineCreationError) -> VulkanInitError {
+        VulkanInitError::Pipeline(err)
+    }
+}
+
+impl From<FramebufferCreationError> for VulkanInitError {
+    fn from(err: FramebufferCreationError) -> VulkanInitError {
+        VulkanInitError::Framebuffer(err)
+    }
+}
+
+impl From<ShaderError> for VulkanInitError {
+    fn from(err: ShaderError) -> VulkanInitError {
+        VulkanInitError::Shader(err)
+    }
+}
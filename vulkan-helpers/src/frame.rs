@@ -0,0 +1,176 @@
+use std::error::Error;
+use std::fmt;
+use std::sync::Arc;
+use vulkano::OomError;
+use vulkano::command_buffer::{AutoCommandBufferBuilder, AutoCommandBufferBuilderContextError,
+                              BeginRenderPassError, BuildError, CommandBufferExecError,
+                              DrawError};
+use vulkano::framebuffer::FramebufferAbstract;
+use vulkano::pipeline::GraphicsPipelineAbstract;
+use vulkano::swapchain::{self, AcquireError};
+use vulkano::sync::{self, FenceSignalFuture, FlushError, GpuFuture};
+
+use context::VulkanContext;
+use error::VulkanInitError;
+
+pub const DEFAULT_FRAMES_IN_FLIGHT: usize = 2;
+
+/// Everything that can go wrong while drawing a frame, other than the swapchain going out of date.
+#[derive(Debug)]
+pub enum FrameError {
+    Acquire(AcquireError),
+    Oom(OomError),
+    BeginRenderPass(BeginRenderPassError),
+    Draw(DrawError),
+    EndRenderPass(AutoCommandBufferBuilderContextError),
+    Build(BuildError),
+    Execute(CommandBufferExecError),
+    Flush(FlushError),
+    Recreate(VulkanInitError),
+}
+
+impl fmt::Display for FrameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FrameError::Acquire(ref err) => write!(f, "failed to acquire image: {}", err),
+            FrameError::Oom(ref err) => write!(f, "failed to allocate command buffer: {}", err),
+            FrameError::BeginRenderPass(ref err) => {
+                write!(f, "failed to begin render pass: {}", err)
+            }
+            FrameError::Draw(ref err) => write!(f, "failed to record draw: {}", err),
+            FrameError::EndRenderPass(ref err) => write!(f, "failed to end render pass: {}", err),
+            FrameError::Build(ref err) => write!(f, "failed to build command buffer: {}", err),
+            FrameError::Execute(ref err) => write!(f, "failed to submit command buffer: {}", err),
+            FrameError::Flush(ref err) => write!(f, "failed to flush frame: {}", err),
+            FrameError::Recreate(ref err) => write!(f, "failed to recreate swapchain: {}", err),
+        }
+    }
+}
+
+impl Error for FrameError {
+    fn source(&self) -> Option<&(Error + 'static)> {
+        match *self {
+            FrameError::Acquire(ref err) => Some(err),
+            FrameError::Oom(ref err) => Some(err),
+            FrameError::BeginRenderPass(ref err) => Some(err),
+            FrameError::Draw(ref err) => Some(err),
+            FrameError::EndRenderPass(ref err) => Some(err),
+            FrameError::Build(ref err) => Some(err),
+            FrameError::Execute(ref err) => Some(err),
+            FrameError::Flush(ref err) => Some(err),
+            FrameError::Recreate(ref err) => Some(err),
+        }
+    }
+}
+
+macro_rules! frame_error_from {
+    ($($err:ident => $variant:ident),*) => {
+        $(
+            impl From<$err> for FrameError {
+                fn from(err: $err) -> FrameError {
+                    FrameError::$variant(err)
+                }
+            }
+        )*
+    };
+}
+
+frame_error_from!(
+    AcquireError => Acquire,
+    OomError => Oom,
+    BeginRenderPassError => BeginRenderPass,
+    DrawError => Draw,
+    AutoCommandBufferBuilderContextError => EndRenderPass,
+    BuildError => Build,
+    CommandBufferExecError => Execute,
+    FlushError => Flush,
+    VulkanInitError => Recreate
+);
+
+type InFlightFuture = Arc<FenceSignalFuture<Box<GpuFuture>>>;
+
+/// Keeps the CPU from running more than `count` frames ahead of the GPU.
+/// Each slot holds the fence of the last frame submitted from it, and is waited on before reuse.
+pub struct FramesInFlight {
+    in_flight: Vec<Option<InFlightFuture>>,
+    current: usize,
+}
+
+impl Default for FramesInFlight {
+    fn default() -> FramesInFlight {
+        FramesInFlight::new(DEFAULT_FRAMES_IN_FLIGHT)
+    }
+}
+
+impl FramesInFlight {
+    pub fn new(count: usize) -> FramesInFlight {
+        FramesInFlight {
+            in_flight: (0..count.max(1)).map(|_| None).collect(),
+            current: 0,
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.in_flight.len()
+    }
+
+    /// Acquires a swapchain image, lets `record` fill in the frame's commands and presents it.
+    ///
+    /// `record` gets a fresh builder, the framebuffer of the acquired image and the context's pipeline
+    /// if it has one. It's up to it to begin and end the render pass.
+    ///
+    /// Returns `false` when no frame was drawn because the swapchain was out of date or the window is minimized.
+    /// The swapchain has been invalidated by then, so just try again next loop.
+    pub fn draw_frame<F>(&mut self, context: &mut VulkanContext, record: F) -> Result<bool, FrameError>
+    where
+        F: FnOnce(
+            AutoCommandBufferBuilder,
+            Arc<FramebufferAbstract + Send + Sync>,
+            Option<Arc<GraphicsPipelineAbstract + Send + Sync>>,
+        ) -> Result<AutoCommandBufferBuilder, FrameError>,
+    {
+        if let Some(previous) = self.in_flight[self.current].take() {
+            previous.wait(None)?;
+        }
+        if !context.recreate_swapchain()? {
+            return Ok(false);
+        }
+        let (image_num, acquire_future) =
+            match swapchain::acquire_next_image(context.swapchain.clone(), None) {
+                Ok(acquired) => acquired,
+                Err(AcquireError::OutOfDate) => {
+                    context.invalidate_swapchain();
+                    return Ok(false);
+                }
+                Err(err) => return Err(err.into()),
+            };
+        let queue = context.graphics_queue.clone();
+        let builder =
+            AutoCommandBufferBuilder::primary_one_time_submit(context.device.clone(), queue.family())?;
+        let command_buffer = record(
+            builder,
+            context.framebuffers[image_num].clone(),
+            context.pipeline.clone(),
+        )?.build()?;
+        let future: Box<GpuFuture> = Box::new(
+            sync::now(context.device.clone())
+                .join(acquire_future)
+                .then_execute(queue, command_buffer)?
+                .then_swapchain_present(
+                    context.present_queue.clone(),
+                    context.swapchain.clone(),
+                    image_num,
+                ),
+        );
+        match future.then_signal_fence_and_flush() {
+            Ok(future) => self.in_flight[self.current] = Some(Arc::new(future)),
+            Err(FlushError::OutOfDate) => {
+                context.invalidate_swapchain();
+                return Ok(false);
+            }
+            Err(err) => return Err(err.into()),
+        }
+        self.current = (self.current + 1) % self.in_flight.len();
+        Ok(true)
+    }
+}
@@ -0,0 +1,76 @@
+use std::sync::Arc;
+use vulkano::instance::{self, Instance, InstanceExtensions, debug::DebugCallback};
+use vulkano_win;
+
+use error::VulkanInitError;
+
+pub fn init_vulkan_instance(layers: &[String]) -> Result<Arc<Instance>, VulkanInitError> {
+    let instance = Instance::new(
+        None,
+        &init_vulkan_instance_extensions(),
+        //INFO (danny): https://github.com/vulkano-rs/vulkano/issues/336
+        layers
+            .iter()
+            .map(|ln| ln.as_str())
+            .collect::<Vec<&str>>()
+            .iter(),
+    )?;
+    Ok(instance)
+}
+
+#[cfg(feature = "vk_debug")]
+fn init_vulkan_instance_extensions() -> InstanceExtensions {
+    println!("Instance Extensions:");
+    let mut extensions = vulkano_win::required_extensions();
+    extensions.ext_debug_report = true;
+    if let Ok(supported) = InstanceExtensions::supported_by_core() {
+        print!("  ✔️ ");
+        println!("{:?}", supported.intersection(&extensions));
+        print!("  ❌ ");
+        println!("{:?}", supported.difference(&extensions));
+    }
+    extensions
+}
+#[cfg(not(feature = "vk_debug"))]
+fn init_vulkan_instance_extensions() -> InstanceExtensions {
+    vulkano_win::required_extensions()
+}
+
+#[cfg(feature = "vk_debug")]
+pub fn init_vulkan_layers() -> Result<Vec<String>, VulkanInitError> {
+    println!("Layers:");
+    let layers = instance::layers_list()?
+        .filter(|layer| {
+            let name = layer.name();
+            let to_activate = name.contains("RENDERDOC") || name.contains("LUNARG");
+            if to_activate {
+                print!("  ✔️ ");
+            } else {
+                print!("  ❌ ");
+            }
+            println!(
+                "{} @ {} - {}",
+                layer.name(),
+                layer.implementation_version(),
+                layer.description()
+            );
+            to_activate
+        })
+        .map(|l| String::from(l.name()))
+        .collect();
+    Ok(layers)
+}
+#[cfg(not(feature = "vk_debug"))]
+pub fn init_vulkan_layers() -> Result<Vec<String>, VulkanInitError> {
+    Ok(vec![])
+}
+
+#[cfg(feature = "vk_debug")]
+pub fn init_vulkan_debug_callbacks(instance: Arc<Instance>) {
+    println!("Setting Up Debug Callbacks.");
+    DebugCallback::errors_and_warnings(&instance, |msg| {
+        println!("Debug callback: {:?}", msg.description);
+    }).ok();
+}
+#[cfg(not(feature = "vk_debug"))]
+pub fn init_vulkan_debug_callbacks(instance: Arc<Instance>) {}
@@ -0,0 +1,58 @@
+extern crate glsl_preprocess;
+extern crate glsl_to_spirv;
+#[macro_use]
+extern crate vulkano;
+extern crate vulkano_shaders;
+extern crate vulkano_win;
+extern crate winit;
+
+mod cache;
+mod context;
+mod device;
+mod error;
+mod frame;
+mod instance;
+mod pipeline;
+mod queues;
+mod reflect;
+mod render_pass;
+mod requirements;
+mod selection;
+mod shader;
+mod shader_error;
+mod surface;
+mod swapchain;
+mod variants;
+mod watcher;
+
+pub use cache::{CacheKey, ShaderCache, COMPILER_VERSION, MAX_ENTRY_AGE_SECS, SHADER_CACHE_ENV_VAR};
+pub use context::{init_vulkan, VulkanContext, VulkanContextBuilder};
+pub use device::{init_device, surface_rejection};
+pub use error::VulkanInitError;
+pub use frame::{FrameError, FramesInFlight, DEFAULT_FRAMES_IN_FLIGHT};
+pub use glsl_preprocess::{preprocess, Diagnostic, IncludeError, Preprocessed, Severity};
+pub use instance::{init_vulkan_debug_callbacks, init_vulkan_instance, init_vulkan_layers};
+pub use pipeline::{create_pipeline, create_shader_stages, PipelineDesc, PipelineInterface,
+                   ShaderInterface, ShaderStages, Specialization};
+pub use queues::{find_queue_families, pick_queue_families, swapchain_sharing_mode, QueueFamilyIndices,
+                 Queues};
+pub use reflect::{reflect, reflect_stage, shader_interface, spirv_words, validate_interface,
+                  validate_specialization, DescriptorBinding, DescriptorKind, EntryPoint,
+                  ExecutionModel, InterfaceMismatch, InterfaceVariable, PushConstantRange,
+                  ReflectError, ReflectedType, ShaderReflection, SpecializationConstant,
+                  SpecializationMismatch};
+pub use render_pass::{create_framebuffers, create_render_pass};
+pub use requirements::DeviceRequirements;
+pub use selection::{rank_devices, score_device, DeviceSelector, RankedDevice, DEVICE_ARG,
+                    DEVICE_ENV_VAR};
+pub use shader::{create_shader, create_shader_module, load_shader, CompileOptions, LoadedShader,
+                 ShaderSource};
+pub use shader_error::ShaderError;
+pub use surface::{framebuffer_dimensions, init_events_loop, init_surface, init_window};
+pub use swapchain::{choose_extent, choose_image_count, choose_present_mode, choose_surface_format,
+                    init_swapchain, ImageCountPolicy, PresentModePolicy, SurfaceFormat,
+                    SwapchainConfig, SwapchainState};
+pub use variants::{ShaderVariants, VariantKey, MAX_FEATURES};
+pub use vulkano::pipeline::shader::{SpecializationConstants, SpecializationMapEntry};
+pub use vulkano_shaders::ShaderType;
+pub use watcher::ShaderWatcher;
@@ -0,0 +1,387 @@
+use std::borrow::Cow;
+use std::ffi::CString;
+use std::fmt;
+use std::fs;
+use std::iter;
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::vec;
+use vulkano::descriptor::pipeline_layout::EmptyPipelineDesc;
+use vulkano::device::Device;
+use vulkano::format::Format;
+use vulkano::framebuffer::{RenderPassAbstract, Subpass};
+use vulkano::pipeline::shader::{GraphicsShaderType, ShaderInterfaceDef, ShaderInterfaceDefEntry,
+                                ShaderModule, SpecializationConstants, SpecializationMapEntry};
+use vulkano::pipeline::vertex::BufferlessDefinition;
+use vulkano::pipeline::viewport::{Scissor, Viewport};
+use vulkano::pipeline::{GraphicsPipeline, GraphicsPipelineAbstract};
+use vulkano_shaders::ShaderType;
+
+use cache::ShaderCache;
+use error::VulkanInitError;
+use reflect::{shader_interface, validate_interface, validate_specialization, ShaderReflection};
+use shader::{load_shader, CompileOptions, LoadedShader, ShaderSource};
+
+/// The inputs or outputs of one shader stage.
+#[derive(Debug, Clone)]
+pub struct ShaderInterface(pub Vec<ShaderInterfaceDefEntry>);
+
+impl ShaderInterface {
+    pub fn empty() -> ShaderInterface {
+        ShaderInterface(vec![])
+    }
+
+    /// Adds a single location variable, like `layout(location = 0) out vec3 fragColor;`.
+    pub fn with(mut self, location: u32, format: Format, name: &'static str) -> ShaderInterface {
+        self.0.push(ShaderInterfaceDefEntry {
+            location: location..location + 1,
+            format,
+            name: Some(Cow::Borrowed(name)),
+        });
+        self
+    }
+}
+
+unsafe impl ShaderInterfaceDef for ShaderInterface {
+    type Iter = vec::IntoIter<ShaderInterfaceDefEntry>;
+
+    fn elements(&self) -> Self::Iter {
+        self.0.clone().into_iter()
+    }
+}
+
+/// How the vertex and fragment shaders talk to each other and to the rest of the pipeline.
+#[derive(Debug, Clone)]
+pub struct PipelineInterface {
+    pub vertex_input: ShaderInterface,
+    pub varyings: ShaderInterface,
+    pub fragment_output: ShaderInterface,
+}
+
+impl PipelineInterface {
+    /// Reads the interface straight out of the shaders' SPIR-V.
+    /// The varyings are what the fragment shader reads, extra vertex outputs are left out.
+    pub fn from_reflection(
+        vertex: &ShaderReflection,
+        fragment: &ShaderReflection,
+    ) -> PipelineInterface {
+        PipelineInterface {
+            vertex_input: shader_interface(&vertex.inputs),
+            varyings: shader_interface(&fragment.inputs),
+            fragment_output: shader_interface(&fragment.outputs),
+        }
+    }
+}
+
+/// Values for the shaders' `layout(constant_id = N) const` declarations, one struct per stage.
+///
+/// Each struct is `#[repr(C)]` and implements vulkano's `SpecializationConstants`, whose entries
+/// map its fields to constant ids. They're checked against the shaders when the pipeline is built.
+/// Use `()` for a stage that doesn't need any.
+#[derive(Clone)]
+pub struct Specialization(Arc<SpecializeStages>);
+
+impl Specialization {
+    pub fn new<V, F>(vertex: V, fragment: F) -> Specialization
+    where
+        V: SpecializationConstants + Copy + Send + Sync + 'static,
+        F: SpecializationConstants + Copy + Send + Sync + 'static,
+    {
+        Specialization(Arc::new((vertex, fragment)))
+    }
+}
+
+impl fmt::Debug for Specialization {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let constant_ids = |entries: &[SpecializationMapEntry]| -> Vec<u32> {
+            entries.iter().map(|entry| entry.constant_id).collect()
+        };
+        f.debug_struct("Specialization")
+            .field("vertex", &constant_ids(self.0.vertex_entries()))
+            .field("fragment", &constant_ids(self.0.fragment_entries()))
+            .finish()
+    }
+}
+
+/// vulkano wants the constants structs as type parameters, so this hides them until the pipeline is built.
+trait SpecializeStages: Send + Sync {
+    fn vertex_entries(&self) -> &'static [SpecializationMapEntry];
+    fn fragment_entries(&self) -> &'static [SpecializationMapEntry];
+    fn create_pipeline(
+        &self,
+        device: Arc<Device>,
+        stages: &ShaderStages,
+        render_pass: Arc<RenderPassAbstract + Send + Sync>,
+        dimensions: [u32; 2],
+    ) -> Result<Arc<GraphicsPipelineAbstract + Send + Sync>, VulkanInitError>;
+}
+
+impl<V, F> SpecializeStages for (V, F)
+where
+    V: SpecializationConstants + Copy + Send + Sync,
+    F: SpecializationConstants + Copy + Send + Sync,
+{
+    fn vertex_entries(&self) -> &'static [SpecializationMapEntry] {
+        V::descriptors()
+    }
+
+    fn fragment_entries(&self) -> &'static [SpecializationMapEntry] {
+        F::descriptors()
+    }
+
+    fn create_pipeline(
+        &self,
+        device: Arc<Device>,
+        stages: &ShaderStages,
+        render_pass: Arc<RenderPassAbstract + Send + Sync>,
+        dimensions: [u32; 2],
+    ) -> Result<Arc<GraphicsPipelineAbstract + Send + Sync>, VulkanInitError> {
+        build_pipeline(device, stages, render_pass, dimensions, self.0, self.1)
+    }
+}
+
+/// Which shaders a `VulkanContext` should build its pipeline from.
+#[derive(Debug, Clone)]
+pub struct PipelineDesc {
+    pub vertex_shader: ShaderSource,
+    pub fragment_shader: ShaderSource,
+    /// Leave this `None` to reflect the interface from the shaders, which also follows them through hot reloads.
+    pub interface: Option<PipelineInterface>,
+    /// Used for both stages, and again when they're hot reloaded.
+    pub compile_options: CompileOptions,
+    pub specialization: Option<Specialization>,
+}
+
+/// Compiled shaders, kept around so the pipeline can be rebuilt without recompiling.
+#[derive(Clone)]
+pub struct ShaderStages {
+    pub vertex: LoadedShader,
+    pub fragment: LoadedShader,
+    pub interface: Option<PipelineInterface>,
+    pub compile_options: CompileOptions,
+    pub specialization: Option<Specialization>,
+    /// What `ShaderSource::name` said for each stage, used to match changed files when hot reloading.
+    pub vertex_name: String,
+    pub fragment_name: String,
+}
+
+impl ShaderStages {
+    /// Which stage was loaded from a file named like `path`, if any.
+    pub fn stage_for(&self, path: &Path) -> Option<ShaderType> {
+        let file_name = path.file_name()?;
+        if Path::new(&self.vertex_name).file_name() == Some(file_name) {
+            Some(ShaderType::Vertex)
+        } else if Path::new(&self.fragment_name).file_name() == Some(file_name) {
+            Some(ShaderType::Fragment)
+        } else {
+            None
+        }
+    }
+
+    /// Which stages need recompiling after `path` changed, and the file to compile each one from.
+    /// That's `path` itself for a stage loaded from a file with the same name,
+    /// or the stage's own file when `path` is one of its includes.
+    pub fn reloads_for(&self, path: &Path) -> Vec<(ShaderType, PathBuf)> {
+        let canonical_path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        let mut reloads = vec![];
+        if let Some(shader_type) = self.stage_for(path) {
+            reloads.push((shader_type, path.to_path_buf()));
+        }
+        let stages = [
+            (ShaderType::Vertex, &self.vertex),
+            (ShaderType::Fragment, &self.fragment),
+        ];
+        for &(ref shader_type, shader) in &stages {
+            if !shader.includes.contains(&canonical_path) {
+                continue;
+            }
+            if let Some(ref source_path) = shader.source_path {
+                reloads.push((shader_type.clone(), source_path.clone()));
+            }
+        }
+        reloads
+    }
+
+    /// The interface given in the `PipelineDesc`, or the reflected one.
+    pub fn interface(&self) -> PipelineInterface {
+        match self.interface {
+            Some(ref interface) => interface.clone(),
+            None => PipelineInterface::from_reflection(
+                &self.vertex.reflection,
+                &self.fragment.reflection,
+            ),
+        }
+    }
+}
+
+pub fn create_shader_stages(
+    device: Arc<Device>,
+    desc: &PipelineDesc,
+    cache: Option<&ShaderCache>,
+) -> Result<ShaderStages, VulkanInitError> {
+    let vertex = load_shader(
+        device.clone(),
+        &desc.vertex_shader,
+        ShaderType::Vertex,
+        &desc.compile_options,
+        cache,
+    )?;
+    let fragment = load_shader(
+        device.clone(),
+        &desc.fragment_shader,
+        ShaderType::Fragment,
+        &desc.compile_options,
+        cache,
+    )?;
+    Ok(ShaderStages {
+        vertex,
+        fragment,
+        interface: desc.interface.clone(),
+        compile_options: desc.compile_options.clone(),
+        specialization: desc.specialization.clone(),
+        vertex_name: desc.vertex_shader.name().to_string(),
+        fragment_name: desc.fragment_shader.name().to_string(),
+    })
+}
+
+/// `main` for GLSL, but HLSL compiled with DXC keeps the name of its entry function.
+fn entry_point_name(shader: &LoadedShader) -> CString {
+    let name = match shader.reflection.entry_point {
+        Some(ref name) => name.clone(),
+        None => String::from("main"),
+    };
+    CString::new(name).expect("SPIR-V strings stop at the first nul")
+}
+
+/// The fixed function state from the tutorial: a triangle list with no vertex buffers,
+/// one viewport and scissor covering `dimensions`, back face culling, no multisampling
+/// and blending turned off. The viewport is baked in, so rebuild this when the swapchain changes size.
+///
+/// The vertex outputs are checked against the fragment inputs first, and any specialization
+/// constants against what the shaders declare, so a mismatch is named instead of failing
+/// somewhere inside the driver. Shaders with descriptors or push constants are refused,
+/// since the pipeline layout is still empty.
+pub fn create_pipeline(
+    device: Arc<Device>,
+    stages: &ShaderStages,
+    render_pass: Arc<RenderPassAbstract + Send + Sync>,
+    dimensions: [u32; 2],
+) -> Result<Arc<GraphicsPipelineAbstract + Send + Sync>, VulkanInitError> {
+    match stages.specialization {
+        Some(ref specialization) => {
+            specialization
+                .0
+                .create_pipeline(device, stages, render_pass, dimensions)
+        }
+        None => build_pipeline(device, stages, render_pass, dimensions, (), ()),
+    }
+}
+
+fn check_specialization<S: SpecializationConstants>(
+    shader: &LoadedShader,
+    name: &str,
+) -> Result<(), VulkanInitError> {
+    let mismatches =
+        validate_specialization(&shader.reflection, S::descriptors(), mem::size_of::<S>());
+    if mismatches.is_empty() {
+        Ok(())
+    } else {
+        Err(VulkanInitError::Specialization {
+            shader: name.to_string(),
+            mismatches,
+        })
+    }
+}
+
+/// Pipelines are built with `EmptyPipelineDesc`, so a shader that uses descriptors
+/// or push constants is refused rather than given a layout that contradicts its SPIR-V.
+fn check_layout(shader: &LoadedShader, name: &str) -> Result<(), VulkanInitError> {
+    let reflection = &shader.reflection;
+    if reflection.descriptor_bindings.is_empty() && reflection.push_constant_ranges.is_empty() {
+        Ok(())
+    } else {
+        Err(VulkanInitError::PipelineLayout {
+            shader: name.to_string(),
+            descriptor_bindings: reflection.descriptor_bindings.clone(),
+            push_constant_ranges: reflection.push_constant_ranges.clone(),
+        })
+    }
+}
+
+fn build_pipeline<V, F>(
+    device: Arc<Device>,
+    stages: &ShaderStages,
+    render_pass: Arc<RenderPassAbstract + Send + Sync>,
+    dimensions: [u32; 2],
+    vertex_constants: V,
+    fragment_constants: F,
+) -> Result<Arc<GraphicsPipelineAbstract + Send + Sync>, VulkanInitError>
+where
+    V: SpecializationConstants,
+    F: SpecializationConstants,
+{
+    let mismatches = validate_interface(&stages.vertex.reflection, &stages.fragment.reflection);
+    if !mismatches.is_empty() {
+        return Err(VulkanInitError::InterfaceMismatch {
+            vertex: stages.vertex_name.clone(),
+            fragment: stages.fragment_name.clone(),
+            mismatches,
+        });
+    }
+    check_specialization::<V>(&stages.vertex, &stages.vertex_name)?;
+    check_specialization::<F>(&stages.fragment, &stages.fragment_name)?;
+    check_layout(&stages.vertex, &stages.vertex_name)?;
+    check_layout(&stages.fragment, &stages.fragment_name)?;
+    let interface = stages.interface();
+    let vertex_main = entry_point_name(&stages.vertex);
+    let fragment_main = entry_point_name(&stages.fragment);
+    let vertex_entry = unsafe {
+        stages.vertex.module.graphics_entry_point(
+            &vertex_main,
+            interface.vertex_input,
+            interface.varyings.clone(),
+            EmptyPipelineDesc,
+            GraphicsShaderType::Vertex,
+        )
+    };
+    let fragment_entry = unsafe {
+        stages.fragment.module.graphics_entry_point(
+            &fragment_main,
+            interface.varyings,
+            interface.fragment_output,
+            EmptyPipelineDesc,
+            GraphicsShaderType::Fragment,
+        )
+    };
+    let viewport = Viewport {
+        origin: [0.0, 0.0],
+        dimensions: [dimensions[0] as f32, dimensions[1] as f32],
+        depth_range: 0.0..1.0,
+    };
+    let scissor = Scissor {
+        origin: [0, 0],
+        dimensions,
+    };
+    let subpass = Subpass::from(render_pass, 0).ok_or(VulkanInitError::NoSubpass)?;
+    let pipeline = GraphicsPipeline::start()
+        .vertex_input(BufferlessDefinition)
+        .vertex_shader(vertex_entry, vertex_constants)
+        .triangle_list()
+        .primitive_restart(false)
+        .viewports_scissors(iter::once((viewport, scissor)))
+        .depth_clamp(false)
+        .polygon_mode_fill()
+        .line_width(1.0)
+        .cull_mode_back()
+        .front_face_clockwise()
+        .sample_shading_disabled()
+        .alpha_to_coverage_disabled()
+        .alpha_to_one_disabled()
+        .fragment_shader(fragment_entry, fragment_constants)
+        .blend_pass_through()
+        .blend_logic_op_disabled()
+        .render_pass(subpass)
+        .build(device)?;
+    Ok(Arc::new(pipeline))
+}
@@ -0,0 +1,134 @@
+use std::sync::Arc;
+use vulkano::device::{Queue, QueuesIter};
+use vulkano::instance::{PhysicalDevice, QueueFamily};
+use vulkano::swapchain::Surface;
+use vulkano::sync::SharingMode;
+use winit;
+
+use error::VulkanInitError;
+
+/// Which queue family each kind of work goes to, like the tutorial's `QueueFamilyIndices`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueueFamilyIndices {
+    pub graphics: u32,
+    pub present: u32,
+    /// A family that only does transfers, for staging uploads.
+    pub transfer: Option<u32>,
+    /// A compute family without graphics, so compute doesn't wait behind rendering.
+    pub compute: Option<u32>,
+}
+
+impl QueueFamilyIndices {
+    /// Every distinct family id, in the order the queues are requested from `Device::new`.
+    pub fn unique(&self) -> Vec<u32> {
+        let mut ids = vec![self.graphics];
+        let optional = [Some(self.present), self.transfer, self.compute];
+        for &id in optional.iter().filter_map(|id| id.as_ref()) {
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+        ids
+    }
+}
+
+pub struct Queues {
+    pub graphics: Arc<Queue>,
+    pub present: Arc<Queue>,
+    pub transfer: Option<Arc<Queue>>,
+    pub compute: Option<Arc<Queue>>,
+}
+
+/// Exclusive when graphics and present share a family, concurrent otherwise.
+pub fn swapchain_sharing_mode(graphics: &Arc<Queue>, present: &Arc<Queue>) -> SharingMode {
+    if graphics.family().id() == present.family().id() {
+        SharingMode::from(graphics)
+    } else {
+        SharingMode::from(&[graphics, present][..])
+    }
+}
+
+/// Prefers a single family that can do both graphics and present.
+pub fn find_queue_families(
+    physical_device: PhysicalDevice,
+    surface: &Surface<winit::Window>,
+) -> Result<QueueFamilyIndices, VulkanInitError> {
+    for queue_family in physical_device.queue_families() {
+        log_queue_family(&queue_family, supports_present(&queue_family, surface));
+    }
+    let indices = pick_queue_families(physical_device, surface)
+        .ok_or(VulkanInitError::NoSuitableQueueFamily)?;
+    println!(
+        "  graphics: {}, present: {}, transfer: {:?}, compute: {:?}",
+        indices.graphics, indices.present, indices.transfer, indices.compute,
+    );
+    Ok(indices)
+}
+
+/// `find_queue_families` without the printing, `None` when there's no graphics or no present family,
+/// like the tutorial's `QueueFamilyIndices::isComplete`.
+pub fn pick_queue_families(
+    physical_device: PhysicalDevice,
+    surface: &Surface<winit::Window>,
+) -> Option<QueueFamilyIndices> {
+    let families: Vec<QueueFamily> = physical_device.queue_families().collect();
+    let supports_present = |qf: &QueueFamily| supports_present(qf, surface);
+    let graphics = families
+        .iter()
+        .find(|qf| qf.supports_graphics() && supports_present(qf))
+        .or_else(|| families.iter().find(|qf| qf.supports_graphics()))?;
+    let present = if supports_present(graphics) {
+        graphics
+    } else {
+        families.iter().find(|qf| supports_present(qf))?
+    };
+    let transfer = families
+        .iter()
+        .find(|qf| qf.supports_transfers() && !qf.supports_graphics() && !qf.supports_compute());
+    let compute = families
+        .iter()
+        .find(|qf| qf.supports_compute() && !qf.supports_graphics());
+    Some(QueueFamilyIndices {
+        graphics: graphics.id(),
+        present: present.id(),
+        transfer: transfer.map(|qf| qf.id()),
+        compute: compute.map(|qf| qf.id()),
+    })
+}
+
+fn supports_present(queue_family: &QueueFamily, surface: &Surface<winit::Window>) -> bool {
+    surface.is_supported(*queue_family).unwrap_or(false)
+}
+
+/// Hands out the queues in the same order `QueueFamilyIndices::unique` requested them.
+pub fn take_queues(
+    indices: &QueueFamilyIndices,
+    queues_iter: QueuesIter,
+) -> Result<Queues, VulkanInitError> {
+    let queues: Vec<(u32, Arc<Queue>)> = indices.unique().into_iter().zip(queues_iter).collect();
+    let queue_for = |id: u32| {
+        queues
+            .iter()
+            .find(|&&(family, _)| family == id)
+            .map(|&(_, ref queue)| queue.clone())
+    };
+    Ok(Queues {
+        graphics: queue_for(indices.graphics).ok_or(VulkanInitError::NoSuitableQueueFamily)?,
+        present: queue_for(indices.present).ok_or(VulkanInitError::NoSuitableQueueFamily)?,
+        transfer: indices.transfer.and_then(&queue_for),
+        compute: indices.compute.and_then(&queue_for),
+    })
+}
+
+fn log_queue_family(queue_family: &QueueFamily, present: bool) {
+    println!(
+        "  id: {}, queues_count: {}, graphics: {}, present: {}, compute: {}, transfers: {}, sparse_binding: {}",
+        queue_family.id(),
+        queue_family.queues_count(),
+        queue_family.supports_graphics(),
+        present,
+        queue_family.supports_compute(),
+        queue_family.supports_transfers(),
+        queue_family.supports_sparse_binding(),
+    );
+}
@@ -0,0 +1,1303 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use vulkano::format::Format;
+use vulkano::pipeline::shader::{ShaderInterfaceDefEntry, SpecializationMapEntry};
+use vulkano_shaders::ShaderType;
+
+use pipeline::ShaderInterface;
+
+const MAGIC: u32 = 0x0723_0203;
+
+// Opcodes, decorations and storage classes from the SPIR-V spec, only the ones we look at.
+const OP_NAME: u32 = 5;
+const OP_ENTRY_POINT: u32 = 15;
+const OP_TYPE_BOOL: u32 = 20;
+const OP_TYPE_INT: u32 = 21;
+const OP_TYPE_FLOAT: u32 = 22;
+const OP_TYPE_VECTOR: u32 = 23;
+const OP_TYPE_MATRIX: u32 = 24;
+const OP_TYPE_IMAGE: u32 = 25;
+const OP_TYPE_SAMPLER: u32 = 26;
+const OP_TYPE_SAMPLED_IMAGE: u32 = 27;
+const OP_TYPE_ARRAY: u32 = 28;
+const OP_TYPE_RUNTIME_ARRAY: u32 = 29;
+const OP_TYPE_STRUCT: u32 = 30;
+const OP_TYPE_POINTER: u32 = 32;
+const OP_CONSTANT: u32 = 43;
+const OP_SPEC_CONSTANT_TRUE: u32 = 48;
+const OP_SPEC_CONSTANT_FALSE: u32 = 49;
+const OP_SPEC_CONSTANT: u32 = 50;
+const OP_VARIABLE: u32 = 59;
+const OP_DECORATE: u32 = 71;
+const OP_MEMBER_DECORATE: u32 = 72;
+
+const DECORATION_SPEC_ID: u32 = 1;
+const DECORATION_BLOCK: u32 = 2;
+const DECORATION_BUFFER_BLOCK: u32 = 3;
+const DECORATION_ARRAY_STRIDE: u32 = 6;
+const DECORATION_MATRIX_STRIDE: u32 = 7;
+const DECORATION_BUILT_IN: u32 = 11;
+const DECORATION_LOCATION: u32 = 30;
+const DECORATION_BINDING: u32 = 33;
+const DECORATION_DESCRIPTOR_SET: u32 = 34;
+const DECORATION_OFFSET: u32 = 35;
+
+const STORAGE_UNIFORM_CONSTANT: u32 = 0;
+const STORAGE_INPUT: u32 = 1;
+const STORAGE_UNIFORM: u32 = 2;
+const STORAGE_OUTPUT: u32 = 3;
+const STORAGE_PUSH_CONSTANT: u32 = 9;
+const STORAGE_STORAGE_BUFFER: u32 = 12;
+
+/// Deeper than any real shader nests its types, and low enough to stop a type that contains itself.
+const MAX_TYPE_DEPTH: u32 = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExecutionModel {
+    Vertex,
+    TessellationControl,
+    TessellationEvaluation,
+    Geometry,
+    Fragment,
+    GLCompute,
+    Other(u32),
+}
+
+impl ExecutionModel {
+    /// What a shader compiled as `shader_type` declares in its entry point.
+    pub fn for_shader_type(shader_type: &ShaderType) -> ExecutionModel {
+        match *shader_type {
+            ShaderType::Vertex => ExecutionModel::Vertex,
+            ShaderType::TessellationControl => ExecutionModel::TessellationControl,
+            ShaderType::TessellationEvaluation => ExecutionModel::TessellationEvaluation,
+            ShaderType::Geometry => ExecutionModel::Geometry,
+            ShaderType::Fragment => ExecutionModel::Fragment,
+            ShaderType::Compute => ExecutionModel::GLCompute,
+        }
+    }
+
+    fn from_word(word: u32) -> ExecutionModel {
+        match word {
+            0 => ExecutionModel::Vertex,
+            1 => ExecutionModel::TessellationControl,
+            2 => ExecutionModel::TessellationEvaluation,
+            3 => ExecutionModel::Geometry,
+            4 => ExecutionModel::Fragment,
+            5 => ExecutionModel::GLCompute,
+            other => ExecutionModel::Other(other),
+        }
+    }
+}
+
+/// A SPIR-V type, just detailed enough to compare interfaces and size push constants.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReflectedType {
+    Bool,
+    Int { width: u32, signed: bool },
+    Float { width: u32 },
+    Vector { component: Box<ReflectedType>, count: u32 },
+    Matrix { column: Box<ReflectedType>, columns: u32 },
+    /// `length` is `None` for runtime sized arrays.
+    Array { element: Box<ReflectedType>, length: Option<u32> },
+    Struct { members: Vec<ReflectedType> },
+    Image,
+    Sampler,
+    SampledImage,
+    Unknown,
+}
+
+impl ReflectedType {
+    /// How many interface locations a variable of this type takes up.
+    pub fn locations(&self) -> u32 {
+        match *self {
+            ReflectedType::Matrix {
+                ref column,
+                columns,
+            } => column.locations().saturating_mul(columns),
+            ReflectedType::Array {
+                ref element,
+                length,
+            } => element.locations().saturating_mul(length.unwrap_or(1)),
+            // 64 bit three and four component vectors spill into a second location.
+            ReflectedType::Vector {
+                ref component,
+                count,
+            } if count > 2 && **component == ReflectedType::Float { width: 64 } => 2,
+            _ => 1,
+        }
+    }
+
+    /// The vertex attribute style format vulkano wants for interface matching.
+    /// Matrices and arrays use the format of a single column or element.
+    pub fn format(&self) -> Option<Format> {
+        let (component, count) = match *self {
+            ReflectedType::Vector {
+                ref component,
+                count,
+            } => (&**component, count),
+            ReflectedType::Matrix { ref column, .. } => return column.format(),
+            ReflectedType::Array { ref element, .. } => return element.format(),
+            ref scalar => (scalar, 1),
+        };
+        let formats = match *component {
+            ReflectedType::Float { width: 32 } => [
+                Format::R32Sfloat,
+                Format::R32G32Sfloat,
+                Format::R32G32B32Sfloat,
+                Format::R32G32B32A32Sfloat,
+            ],
+            ReflectedType::Float { width: 64 } => [
+                Format::R64Sfloat,
+                Format::R64G64Sfloat,
+                Format::R64G64B64Sfloat,
+                Format::R64G64B64A64Sfloat,
+            ],
+            ReflectedType::Int {
+                width: 32,
+                signed: true,
+            } => [
+                Format::R32Sint,
+                Format::R32G32Sint,
+                Format::R32G32B32Sint,
+                Format::R32G32B32A32Sint,
+            ],
+            ReflectedType::Int {
+                width: 32,
+                signed: false,
+            } => [
+                Format::R32Uint,
+                Format::R32G32Uint,
+                Format::R32G32B32Uint,
+                Format::R32G32B32A32Uint,
+            ],
+            _ => return None,
+        };
+        if count == 0 {
+            return None;
+        }
+        formats.get(count as usize - 1).cloned()
+    }
+}
+
+impl fmt::Display for ReflectedType {
+    /// GLSL spelling, like `vec3` or `float[4]`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ReflectedType::Bool => write!(f, "bool"),
+            ReflectedType::Int {
+                width: 32,
+                signed: true,
+            } => write!(f, "int"),
+            ReflectedType::Int {
+                width: 32,
+                signed: false,
+            } => write!(f, "uint"),
+            ReflectedType::Int { width, signed } => {
+                write!(f, "{}int{}", if signed { "" } else { "u" }, width)
+            }
+            ReflectedType::Float { width: 32 } => write!(f, "float"),
+            ReflectedType::Float { width: 64 } => write!(f, "double"),
+            ReflectedType::Float { width } => write!(f, "float{}", width),
+            ReflectedType::Vector {
+                ref component,
+                count,
+            } => {
+                let prefix = match **component {
+                    ReflectedType::Float { width: 32 } => "",
+                    ReflectedType::Float { width: 64 } => "d",
+                    ReflectedType::Int { signed: true, .. } => "i",
+                    ReflectedType::Int { signed: false, .. } => "u",
+                    ReflectedType::Bool => "b",
+                    _ => "?",
+                };
+                write!(f, "{}vec{}", prefix, count)
+            }
+            ReflectedType::Matrix {
+                ref column,
+                columns,
+            } => match **column {
+                ReflectedType::Vector { count, .. } if count == columns => write!(f, "mat{}", columns),
+                ReflectedType::Vector { count, .. } => write!(f, "mat{}x{}", columns, count),
+                _ => write!(f, "mat?"),
+            },
+            ReflectedType::Array {
+                ref element,
+                length: Some(length),
+            } => write!(f, "{}[{}]", element, length),
+            ReflectedType::Array {
+                ref element,
+                length: None,
+            } => write!(f, "{}[]", element),
+            ReflectedType::Struct { .. } => write!(f, "struct"),
+            ReflectedType::Image => write!(f, "image"),
+            ReflectedType::Sampler => write!(f, "sampler"),
+            ReflectedType::SampledImage => write!(f, "sampler2D"),
+            ReflectedType::Unknown => write!(f, "?"),
+        }
+    }
+}
+
+/// A `layout(location = N) in` or `out` variable. Built-ins like `gl_Position` aren't included.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InterfaceVariable {
+    pub location: u32,
+    pub name: Option<String>,
+    pub ty: ReflectedType,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DescriptorKind {
+    UniformBuffer,
+    StorageBuffer,
+    Image,
+    Sampler,
+    CombinedImageSampler,
+    Other,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DescriptorBinding {
+    pub set: u32,
+    pub binding: u32,
+    pub name: Option<String>,
+    pub kind: DescriptorKind,
+    /// Greater than one for arrays of descriptors.
+    pub count: u32,
+}
+
+/// A `layout(constant_id = N) const` declaration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpecializationConstant {
+    pub constant_id: u32,
+    pub name: Option<String>,
+    pub ty: ReflectedType,
+}
+
+impl SpecializationConstant {
+    /// How many bytes the value takes in the specialization data. Booleans are a 32 bit `VkBool32`.
+    pub fn size(&self) -> usize {
+        match self.ty {
+            ReflectedType::Bool => 4,
+            ReflectedType::Int { width, .. } | ReflectedType::Float { width } => width as usize / 8,
+            _ => 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PushConstantRange {
+    pub name: Option<String>,
+    pub offset: u32,
+    pub size: u32,
+}
+
+/// One `OpEntryPoint`. glslang writes a single one, DXC libraries and `spirv-link` can write one per stage.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntryPoint {
+    pub execution_model: ExecutionModel,
+    /// `main` for GLSL, whatever the entry function was called for HLSL.
+    pub name: String,
+}
+
+/// What a compiled shader module expects from the pipeline around it.
+#[derive(Debug, Clone, Default)]
+pub struct ShaderReflection {
+    /// The stage of the entry point this was reflected for, `None` if the module has none for it.
+    pub execution_model: Option<ExecutionModel>,
+    /// The name of that entry point.
+    pub entry_point: Option<String>,
+    /// Every entry point in the module.
+    pub entry_points: Vec<EntryPoint>,
+    pub inputs: Vec<InterfaceVariable>,
+    pub outputs: Vec<InterfaceVariable>,
+    pub descriptor_bindings: Vec<DescriptorBinding>,
+    pub push_constant_ranges: Vec<PushConstantRange>,
+    pub specialization_constants: Vec<SpecializationConstant>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReflectError {
+    NotWordAligned(usize),
+    TooShort,
+    BadMagic(u32),
+    Truncated { word: usize },
+    /// A type nested more than `MAX_TYPE_DEPTH` deep, which in practice means it contains itself.
+    TypeTooDeep { id: u32 },
+}
+
+impl fmt::Display for ReflectError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ReflectError::NotWordAligned(len) => {
+                write!(f, "{} bytes isn't a whole number of SPIR-V words", len)
+            }
+            ReflectError::TooShort => write!(f, "too short to hold a SPIR-V header"),
+            ReflectError::BadMagic(magic) => {
+                write!(f, "bad SPIR-V magic number {:#010x}, expected {:#010x}", magic, MAGIC)
+            }
+            ReflectError::Truncated { word } => write!(f, "instruction at word {} runs past the end", word),
+            ReflectError::TypeTooDeep { id } => {
+                write!(f, "type %{} nests too deep, it probably contains itself", id)
+            }
+        }
+    }
+}
+
+impl Error for ReflectError {}
+
+/// Turns SPIR-V bytes into words, checking the magic number and swapping big endian modules.
+pub fn spirv_words(bytes: &[u8]) -> Result<Vec<u32>, ReflectError> {
+    if bytes.len() % 4 != 0 {
+        return Err(ReflectError::NotWordAligned(bytes.len()));
+    }
+    if bytes.len() < 20 {
+        return Err(ReflectError::TooShort);
+    }
+    let mut words: Vec<u32> = bytes
+        .chunks(4)
+        .map(|b| u32::from(b[0]) | u32::from(b[1]) << 8 | u32::from(b[2]) << 16 | u32::from(b[3]) << 24)
+        .collect();
+    if words[0] == MAGIC.swap_bytes() {
+        for word in &mut words {
+            *word = word.swap_bytes();
+        }
+    }
+    if words[0] != MAGIC {
+        return Err(ReflectError::BadMagic(words[0]));
+    }
+    Ok(words)
+}
+
+#[derive(Debug, Clone)]
+enum RawType {
+    Bool,
+    Int(u32, bool),
+    Float(u32),
+    Vector(u32, u32),
+    Matrix(u32, u32),
+    Image,
+    Sampler,
+    SampledImage,
+    Array(u32, u32),
+    RuntimeArray(u32),
+    Struct(Vec<u32>),
+    Pointer(u32, u32),
+}
+
+#[derive(Debug, Default)]
+struct Decorations {
+    location: Option<u32>,
+    binding: Option<u32>,
+    set: Option<u32>,
+    array_stride: Option<u32>,
+    spec_id: Option<u32>,
+    built_in: bool,
+    block: bool,
+    buffer_block: bool,
+}
+
+#[derive(Debug, Default)]
+struct Module {
+    /// Each entry point and the ids of the variables in its interface.
+    entry_points: Vec<(EntryPoint, Vec<u32>)>,
+    names: HashMap<u32, String>,
+    decorations: HashMap<u32, Decorations>,
+    member_offsets: HashMap<(u32, u32), u32>,
+    member_matrix_strides: HashMap<(u32, u32), u32>,
+    types: HashMap<u32, RawType>,
+    constants: HashMap<u32, u32>,
+    /// Result id and type id of each `OpSpecConstant*`.
+    spec_constants: Vec<(u32, u32)>,
+    variables: Vec<(u32, u32, u32)>,
+}
+
+/// Reflects a module for its first entry point.
+pub fn reflect(bytes: &[u8]) -> Result<ShaderReflection, ReflectError> {
+    let module = parse(&spirv_words(bytes)?)?;
+    reflect_entry_point(&module, 0)
+}
+
+/// Reflects a module for its entry point with `execution_model`, like the vertex half of a module
+/// that holds a whole pipeline. `execution_model` is `None` in the result when there's no such entry point.
+pub fn reflect_stage(
+    bytes: &[u8],
+    execution_model: ExecutionModel,
+) -> Result<ShaderReflection, ReflectError> {
+    let module = parse(&spirv_words(bytes)?)?;
+    let index = module
+        .entry_points
+        .iter()
+        .position(|&(ref entry_point, _)| entry_point.execution_model == execution_model)
+        .unwrap_or_else(|| module.entry_points.len());
+    reflect_entry_point(&module, index)
+}
+
+/// `index` past the end of the entry points reflects everything but the entry point itself.
+fn reflect_entry_point(module: &Module, index: usize) -> Result<ShaderReflection, ReflectError> {
+    let entry_point = module.entry_points.get(index);
+    let mut reflection = ShaderReflection {
+        execution_model: entry_point.map(|&(ref entry_point, _)| entry_point.execution_model),
+        entry_point: entry_point.map(|&(ref entry_point, _)| entry_point.name.clone()),
+        entry_points: module
+            .entry_points
+            .iter()
+            .map(|&(ref entry_point, _)| entry_point.clone())
+            .collect(),
+        ..ShaderReflection::default()
+    };
+    // With several entry points each one only sees the inputs and outputs it lists.
+    let interface = match entry_point {
+        Some(&(_, ref interface)) if module.entry_points.len() > 1 => Some(interface),
+        _ => None,
+    };
+    for &(id, pointer, storage) in &module.variables {
+        let pointee = match module.types.get(&pointer) {
+            Some(&RawType::Pointer(_, pointee)) => pointee,
+            _ => continue,
+        };
+        let decorations = module.decorations.get(&id);
+        let name = module.names.get(&id).cloned();
+        match storage {
+            STORAGE_INPUT | STORAGE_OUTPUT => {
+                if interface.map_or(false, |interface| !interface.contains(&id)) {
+                    continue;
+                }
+                let location = match decorations.and_then(|d| if d.built_in { None } else { d.location }) {
+                    Some(location) => location,
+                    None => continue,
+                };
+                let variable = InterfaceVariable {
+                    location,
+                    name,
+                    ty: module.resolve(pointee)?,
+                };
+                if storage == STORAGE_INPUT {
+                    reflection.inputs.push(variable);
+                } else {
+                    reflection.outputs.push(variable);
+                }
+            }
+            STORAGE_UNIFORM_CONSTANT | STORAGE_UNIFORM | STORAGE_STORAGE_BUFFER => {
+                let (set, binding) = match decorations {
+                    Some(d) => (d.set.unwrap_or(0), d.binding.unwrap_or(0)),
+                    None => (0, 0),
+                };
+                let (element, count) = match module.types.get(&pointee) {
+                    Some(&RawType::Array(element, length)) => {
+                        (element, module.constants.get(&length).cloned().unwrap_or(1))
+                    }
+                    _ => (pointee, 1),
+                };
+                reflection.descriptor_bindings.push(DescriptorBinding {
+                    set,
+                    binding,
+                    name,
+                    kind: module.descriptor_kind(storage, element),
+                    count,
+                });
+            }
+            STORAGE_PUSH_CONSTANT => {
+                // The block can start past zero when another stage owns the first bytes.
+                let offset = module.first_member_offset(pointee);
+                let end = module.size_of(pointee)?;
+                reflection.push_constant_ranges.push(PushConstantRange {
+                    name,
+                    offset,
+                    size: end.saturating_sub(offset),
+                });
+            }
+            _ => (),
+        }
+    }
+    for &(id, ty) in &module.spec_constants {
+        let constant_id = match module.decorations.get(&id).and_then(|d| d.spec_id) {
+            Some(constant_id) => constant_id,
+            None => continue,
+        };
+        reflection.specialization_constants.push(SpecializationConstant {
+            constant_id,
+            name: module.names.get(&id).cloned(),
+            ty: module.resolve(ty)?,
+        });
+    }
+    reflection.inputs.sort_by_key(|variable| variable.location);
+    reflection.outputs.sort_by_key(|variable| variable.location);
+    reflection
+        .descriptor_bindings
+        .sort_by_key(|binding| (binding.set, binding.binding));
+    reflection
+        .specialization_constants
+        .sort_by_key(|constant| constant.constant_id);
+    Ok(reflection)
+}
+
+fn parse(words: &[u32]) -> Result<Module, ReflectError> {
+    let mut module = Module::default();
+    let mut i = 5;
+    while i < words.len() {
+        let word_count = (words[i] >> 16) as usize;
+        let opcode = words[i] & 0xffff;
+        if word_count == 0 || i + word_count > words.len() {
+            return Err(ReflectError::Truncated { word: i });
+        }
+        let operands = &words[i + 1..i + word_count];
+        module.instruction(opcode, operands);
+        i += word_count;
+    }
+    Ok(module)
+}
+
+impl Module {
+    fn instruction(&mut self, opcode: u32, operands: &[u32]) {
+        if operands.is_empty() {
+            return;
+        }
+        // Types that are nothing but a result id.
+        match opcode {
+            OP_TYPE_BOOL => {
+                self.types.insert(operands[0], RawType::Bool);
+                return;
+            }
+            OP_TYPE_SAMPLER => {
+                self.types.insert(operands[0], RawType::Sampler);
+                return;
+            }
+            _ => (),
+        }
+        // Everything else we care about has at least two operands.
+        if operands.len() < 2 {
+            return;
+        }
+        match opcode {
+            OP_NAME => {
+                self.names.insert(operands[0], literal_string(&operands[1..]));
+            }
+            OP_ENTRY_POINT if operands.len() >= 3 => {
+                let name = literal_string(&operands[2..]);
+                // The name takes up every word up to and including the one with its nul.
+                let name_words = operands[2..]
+                    .iter()
+                    .position(|&word| (0..4).any(|byte| (word >> (byte * 8)) & 0xff == 0))
+                    .map_or(operands.len() - 2, |last| last + 1);
+                let entry_point = EntryPoint {
+                    execution_model: ExecutionModel::from_word(operands[0]),
+                    name,
+                };
+                self.entry_points
+                    .push((entry_point, operands[2 + name_words..].to_vec()));
+            }
+            OP_TYPE_INT if operands.len() >= 3 => {
+                self.types
+                    .insert(operands[0], RawType::Int(operands[1], operands[2] != 0));
+            }
+            OP_TYPE_FLOAT => {
+                self.types.insert(operands[0], RawType::Float(operands[1]));
+            }
+            OP_TYPE_VECTOR if operands.len() >= 3 => {
+                self.types
+                    .insert(operands[0], RawType::Vector(operands[1], operands[2]));
+            }
+            OP_TYPE_MATRIX if operands.len() >= 3 => {
+                self.types
+                    .insert(operands[0], RawType::Matrix(operands[1], operands[2]));
+            }
+            OP_TYPE_IMAGE => {
+                self.types.insert(operands[0], RawType::Image);
+            }
+            OP_TYPE_SAMPLED_IMAGE => {
+                self.types.insert(operands[0], RawType::SampledImage);
+            }
+            OP_TYPE_ARRAY if operands.len() >= 3 => {
+                self.types
+                    .insert(operands[0], RawType::Array(operands[1], operands[2]));
+            }
+            OP_TYPE_RUNTIME_ARRAY => {
+                self.types
+                    .insert(operands[0], RawType::RuntimeArray(operands[1]));
+            }
+            OP_TYPE_STRUCT => {
+                self.types
+                    .insert(operands[0], RawType::Struct(operands[1..].to_vec()));
+            }
+            OP_TYPE_POINTER if operands.len() >= 3 => {
+                self.types
+                    .insert(operands[0], RawType::Pointer(operands[1], operands[2]));
+            }
+            OP_CONSTANT if operands.len() >= 3 => {
+                self.constants.insert(operands[1], operands[2]);
+            }
+            OP_SPEC_CONSTANT_TRUE | OP_SPEC_CONSTANT_FALSE | OP_SPEC_CONSTANT => {
+                self.spec_constants.push((operands[1], operands[0]));
+            }
+            OP_VARIABLE if operands.len() >= 3 => {
+                self.variables.push((operands[1], operands[0], operands[2]));
+            }
+            OP_DECORATE => {
+                let decorations = self.decorations.entry(operands[0]).or_default();
+                let value = operands.get(2).cloned();
+                match operands[1] {
+                    DECORATION_LOCATION => decorations.location = value,
+                    DECORATION_BINDING => decorations.binding = value,
+                    DECORATION_DESCRIPTOR_SET => decorations.set = value,
+                    DECORATION_ARRAY_STRIDE => decorations.array_stride = value,
+                    DECORATION_SPEC_ID => decorations.spec_id = value,
+                    DECORATION_BUILT_IN => decorations.built_in = true,
+                    DECORATION_BLOCK => decorations.block = true,
+                    DECORATION_BUFFER_BLOCK => decorations.buffer_block = true,
+                    _ => (),
+                }
+            }
+            OP_MEMBER_DECORATE if operands.len() >= 4 => {
+                let member = (operands[0], operands[1]);
+                match operands[2] {
+                    DECORATION_OFFSET => {
+                        self.member_offsets.insert(member, operands[3]);
+                    }
+                    DECORATION_MATRIX_STRIDE => {
+                        self.member_matrix_strides.insert(member, operands[3]);
+                    }
+                    _ => (),
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn resolve(&self, id: u32) -> Result<ReflectedType, ReflectError> {
+        self.resolve_nested(id, 0)
+    }
+
+    fn resolve_nested(&self, id: u32, depth: u32) -> Result<ReflectedType, ReflectError> {
+        if depth > MAX_TYPE_DEPTH {
+            return Err(ReflectError::TypeTooDeep { id });
+        }
+        let inner = |id| self.resolve_nested(id, depth + 1).map(Box::new);
+        Ok(match self.types.get(&id) {
+            Some(&RawType::Bool) => ReflectedType::Bool,
+            Some(&RawType::Int(width, signed)) => ReflectedType::Int { width, signed },
+            Some(&RawType::Float(width)) => ReflectedType::Float { width },
+            Some(&RawType::Vector(component, count)) => ReflectedType::Vector {
+                component: inner(component)?,
+                count,
+            },
+            Some(&RawType::Matrix(column, columns)) => ReflectedType::Matrix {
+                column: inner(column)?,
+                columns,
+            },
+            Some(&RawType::Array(element, length)) => ReflectedType::Array {
+                element: inner(element)?,
+                length: self.constants.get(&length).cloned(),
+            },
+            Some(&RawType::RuntimeArray(element)) => ReflectedType::Array {
+                element: inner(element)?,
+                length: None,
+            },
+            Some(&RawType::Struct(ref members)) => ReflectedType::Struct {
+                members: members
+                    .iter()
+                    .map(|&member| self.resolve_nested(member, depth + 1))
+                    .collect::<Result<_, _>>()?,
+            },
+            Some(&RawType::Image) => ReflectedType::Image,
+            Some(&RawType::Sampler) => ReflectedType::Sampler,
+            Some(&RawType::SampledImage) => ReflectedType::SampledImage,
+            Some(&RawType::Pointer(_, pointee)) => return self.resolve_nested(pointee, depth + 1),
+            None => ReflectedType::Unknown,
+        })
+    }
+
+    fn descriptor_kind(&self, storage: u32, id: u32) -> DescriptorKind {
+        let decorations = self.decorations.get(&id);
+        match (storage, self.types.get(&id)) {
+            (STORAGE_STORAGE_BUFFER, _) => DescriptorKind::StorageBuffer,
+            (STORAGE_UNIFORM, _) if decorations.map_or(false, |d| d.buffer_block) => {
+                DescriptorKind::StorageBuffer
+            }
+            (STORAGE_UNIFORM, _) if decorations.map_or(false, |d| d.block) => {
+                DescriptorKind::UniformBuffer
+            }
+            (_, Some(&RawType::SampledImage)) => DescriptorKind::CombinedImageSampler,
+            (_, Some(&RawType::Image)) => DescriptorKind::Image,
+            (_, Some(&RawType::Sampler)) => DescriptorKind::Sampler,
+            _ => DescriptorKind::Other,
+        }
+    }
+
+    /// Byte size using the explicit offsets and strides GLSL puts on blocks.
+    /// For a struct that's where its last member ends, counting from the start of the block.
+    fn size_of(&self, id: u32) -> Result<u32, ReflectError> {
+        self.size_of_nested(id, 0)
+    }
+
+    fn size_of_nested(&self, id: u32, depth: u32) -> Result<u32, ReflectError> {
+        if depth > MAX_TYPE_DEPTH {
+            return Err(ReflectError::TypeTooDeep { id });
+        }
+        let size_of = |id| self.size_of_nested(id, depth + 1);
+        Ok(match self.types.get(&id) {
+            Some(&RawType::Bool) => 4,
+            Some(&RawType::Int(width, _)) | Some(&RawType::Float(width)) => width / 8,
+            Some(&RawType::Vector(component, count)) => size_of(component)?.saturating_mul(count),
+            Some(&RawType::Matrix(column, columns)) => size_of(column)?.saturating_mul(columns),
+            Some(&RawType::Array(element, length)) => {
+                let length = self.constants.get(&length).cloned().unwrap_or(1);
+                let stride = match self.decorations.get(&id).and_then(|d| d.array_stride) {
+                    Some(stride) => stride,
+                    None => size_of(element)?,
+                };
+                stride.saturating_mul(length)
+            }
+            Some(&RawType::Struct(ref members)) => {
+                let mut end = 0;
+                for (index, &member) in members.iter().enumerate() {
+                    let key = (id, index as u32);
+                    let offset = self.member_offsets.get(&key).cloned().unwrap_or(0);
+                    let size = match (self.types.get(&member), self.member_matrix_strides.get(&key)) {
+                        (Some(&RawType::Matrix(_, columns)), Some(&stride)) => stride.saturating_mul(columns),
+                        _ => size_of(member)?,
+                    };
+                    end = end.max(offset.saturating_add(size));
+                }
+                end
+            }
+            _ => 0,
+        })
+    }
+
+    /// The smallest member `Offset` of a struct, zero for anything else.
+    fn first_member_offset(&self, id: u32) -> u32 {
+        match self.types.get(&id) {
+            Some(&RawType::Struct(ref members)) => (0..members.len() as u32)
+                .filter_map(|index| self.member_offsets.get(&(id, index)).cloned())
+                .min()
+                .unwrap_or(0),
+            _ => 0,
+        }
+    }
+}
+
+/// SPIR-V strings are nul terminated UTF-8 packed little end first into words.
+fn literal_string(words: &[u32]) -> String {
+    let mut bytes = Vec::new();
+    'words: for &word in words {
+        for &shift in &[0, 8, 16, 24] {
+            let byte = (word >> shift) as u8;
+            if byte == 0 {
+                break 'words;
+            }
+            bytes.push(byte);
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+/// One location where what the vertex shader writes doesn't line up with what the fragment shader reads.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InterfaceMismatch {
+    pub location: u32,
+    pub vertex_output: Option<InterfaceVariable>,
+    pub fragment_input: InterfaceVariable,
+}
+
+impl fmt::Display for InterfaceMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let describe = |variable: &InterfaceVariable| match variable.name {
+            Some(ref name) => format!("{} {}", variable.ty, name),
+            None => format!("{}", variable.ty),
+        };
+        match self.vertex_output {
+            Some(ref output) => write!(
+                f,
+                "location {}: vertex shader writes `{}` but fragment shader reads `{}`",
+                self.location,
+                describe(output),
+                describe(&self.fragment_input),
+            ),
+            None => write!(
+                f,
+                "location {}: fragment shader reads `{}` but vertex shader never writes it",
+                self.location,
+                describe(&self.fragment_input),
+            ),
+        }
+    }
+}
+
+/// Every fragment input needs a vertex output at the same location with the same type.
+/// Extra vertex outputs are fine, Vulkan just drops them.
+pub fn validate_interface(
+    vertex: &ShaderReflection,
+    fragment: &ShaderReflection,
+) -> Vec<InterfaceMismatch> {
+    fragment
+        .inputs
+        .iter()
+        .filter_map(|input| {
+            let output = vertex
+                .outputs
+                .iter()
+                .find(|output| output.location == input.location);
+            match output {
+                Some(output) if output.ty == input.ty => None,
+                _ => Some(InterfaceMismatch {
+                    location: input.location,
+                    vertex_output: output.cloned(),
+                    fragment_input: input.clone(),
+                }),
+            }
+        })
+        .collect()
+}
+
+/// Something wrong with how a specialization constants struct lines up with the shader.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SpecializationMismatch {
+    UnknownConstant {
+        constant_id: u32,
+    },
+    Size {
+        constant_id: u32,
+        ty: ReflectedType,
+        size: usize,
+    },
+    OutOfBounds {
+        constant_id: u32,
+        offset: u32,
+        size: usize,
+        struct_size: usize,
+    },
+}
+
+impl fmt::Display for SpecializationMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SpecializationMismatch::UnknownConstant { constant_id } => {
+                write!(f, "constant_id {} isn't declared by the shader", constant_id)
+            }
+            SpecializationMismatch::Size {
+                constant_id,
+                ref ty,
+                size,
+            } => write!(
+                f,
+                "constant_id {} is a `{}` in the shader but {} bytes in the struct",
+                constant_id, ty, size
+            ),
+            SpecializationMismatch::OutOfBounds {
+                constant_id,
+                offset,
+                size,
+                struct_size,
+            } => write!(
+                f,
+                "constant_id {} at offset {} with size {} runs past the end of the {} byte struct",
+                constant_id, offset, size, struct_size
+            ),
+        }
+    }
+}
+
+/// Checks every entry of a specialization constants struct names a constant the shader declares,
+/// has the right size for it and fits inside the struct. Constants left out keep their GLSL defaults.
+pub fn validate_specialization(
+    reflection: &ShaderReflection,
+    entries: &[SpecializationMapEntry],
+    struct_size: usize,
+) -> Vec<SpecializationMismatch> {
+    entries
+        .iter()
+        .filter_map(|entry| {
+            let constant_id = entry.constant_id;
+            if entry.offset as usize + entry.size > struct_size {
+                return Some(SpecializationMismatch::OutOfBounds {
+                    constant_id,
+                    offset: entry.offset,
+                    size: entry.size,
+                    struct_size,
+                });
+            }
+            let constant = reflection
+                .specialization_constants
+                .iter()
+                .find(|constant| constant.constant_id == constant_id);
+            match constant {
+                None => Some(SpecializationMismatch::UnknownConstant { constant_id }),
+                Some(constant) if constant.size() != entry.size => Some(SpecializationMismatch::Size {
+                    constant_id,
+                    ty: constant.ty.clone(),
+                    size: entry.size,
+                }),
+                Some(_) => None,
+            }
+        })
+        .collect()
+}
+
+/// The vulkano interface for a list of reflected variables, skipping types it has no format for.
+pub fn shader_interface(variables: &[InterfaceVariable]) -> ShaderInterface {
+    ShaderInterface(
+        variables
+            .iter()
+            .filter_map(|variable| {
+                variable.ty.format().map(|format| ShaderInterfaceDefEntry {
+                    location: variable.location..variable.location.saturating_add(variable.ty.locations()),
+                    format,
+                    name: variable.name.clone().map(|name| name.into()),
+                })
+            })
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FLOAT: u32 = 1;
+
+    fn op(opcode: u32, operands: &[u32]) -> Vec<u32> {
+        let mut words = vec![(operands.len() as u32 + 1) << 16 | opcode];
+        words.extend_from_slice(operands);
+        words
+    }
+
+    /// A nul terminated string packed into words the way SPIR-V wants it.
+    fn string(s: &str) -> Vec<u32> {
+        let mut bytes = s.as_bytes().to_vec();
+        bytes.push(0);
+        while bytes.len() % 4 != 0 {
+            bytes.push(0);
+        }
+        bytes
+            .chunks(4)
+            .map(|b| {
+                u32::from(b[0])
+                    | u32::from(b[1]) << 8
+                    | u32::from(b[2]) << 16
+                    | u32::from(b[3]) << 24
+            })
+            .collect()
+    }
+
+    fn entry_point(model: u32, name: &str, interface: &[u32]) -> Vec<u32> {
+        let mut operands = vec![model, 2];
+        operands.extend(string(name));
+        operands.extend_from_slice(interface);
+        op(OP_ENTRY_POINT, &operands)
+    }
+
+    fn module(instructions: &[Vec<u32>]) -> Vec<u8> {
+        let mut words = vec![MAGIC, 0x0001_0000, 0, 100, 0];
+        for instruction in instructions {
+            words.extend_from_slice(instruction);
+        }
+        let mut bytes = Vec::new();
+        for word in words {
+            bytes.extend_from_slice(&[
+                word as u8,
+                (word >> 8) as u8,
+                (word >> 16) as u8,
+                (word >> 24) as u8,
+            ]);
+        }
+        bytes
+    }
+
+    /// One entry point whose variables are float vectors in `storage`, one per `(location, count)`.
+    /// A location of `None` makes it a built-in.
+    fn interface_module(model: u32, storage: u32, variables: &[(Option<u32>, u32)]) -> Vec<u8> {
+        let ids: Vec<u32> = (0..variables.len() as u32).map(|i| 30 + i).collect();
+        let mut instructions = vec![
+            entry_point(model, "main", &ids),
+            op(OP_TYPE_FLOAT, &[FLOAT, 32]),
+        ];
+        for (i, &(location, count)) in variables.iter().enumerate() {
+            let i = i as u32;
+            instructions.push(op(
+                OP_NAME,
+                &[[30 + i].to_vec(), string(&format!("v{}", i))].concat(),
+            ));
+            instructions.push(match location {
+                Some(location) => op(OP_DECORATE, &[30 + i, DECORATION_LOCATION, location]),
+                None => op(OP_DECORATE, &[30 + i, DECORATION_BUILT_IN, 0]),
+            });
+            instructions.push(op(OP_TYPE_VECTOR, &[10 + i, FLOAT, count]));
+            instructions.push(op(OP_TYPE_POINTER, &[20 + i, storage, 10 + i]));
+            instructions.push(op(OP_VARIABLE, &[20 + i, 30 + i, storage]));
+        }
+        module(&instructions)
+    }
+
+    fn vec(count: u32) -> ReflectedType {
+        ReflectedType::Vector {
+            component: Box::new(ReflectedType::Float { width: 32 }),
+            count,
+        }
+    }
+
+    #[test]
+    fn matching_interface_has_no_mismatches() {
+        let vertex = reflect(&interface_module(
+            0,
+            STORAGE_OUTPUT,
+            &[(Some(0), 3), (Some(1), 2)],
+        ))
+        .unwrap();
+        let fragment = reflect(&interface_module(
+            4,
+            STORAGE_INPUT,
+            &[(Some(1), 2), (Some(0), 3)],
+        ))
+        .unwrap();
+        assert_eq!(vertex.execution_model, Some(ExecutionModel::Vertex));
+        assert_eq!(fragment.execution_model, Some(ExecutionModel::Fragment));
+        assert_eq!(fragment.inputs[0].location, 0);
+        assert_eq!(fragment.inputs[0].name, Some("v1".to_string()));
+        assert!(validate_interface(&vertex, &fragment).is_empty());
+    }
+
+    #[test]
+    fn interface_mismatch_by_location() {
+        let vertex = reflect(&interface_module(0, STORAGE_OUTPUT, &[(Some(0), 4)])).unwrap();
+        let fragment = reflect(&interface_module(4, STORAGE_INPUT, &[(Some(1), 4)])).unwrap();
+        let mismatches = validate_interface(&vertex, &fragment);
+        assert_eq!(mismatches.len(), 1);
+        assert_eq!(mismatches[0].location, 1);
+        assert_eq!(mismatches[0].vertex_output, None);
+        assert_eq!(
+            mismatches[0].to_string(),
+            "location 1: fragment shader reads `vec4 v0` but vertex shader never writes it"
+        );
+    }
+
+    #[test]
+    fn interface_mismatch_by_type() {
+        let vertex = reflect(&interface_module(0, STORAGE_OUTPUT, &[(Some(0), 3)])).unwrap();
+        let fragment = reflect(&interface_module(4, STORAGE_INPUT, &[(Some(0), 4)])).unwrap();
+        let mismatches = validate_interface(&vertex, &fragment);
+        assert_eq!(mismatches.len(), 1);
+        assert_eq!(
+            mismatches[0]
+                .vertex_output
+                .as_ref()
+                .map(|output| &output.ty),
+            Some(&vec(3))
+        );
+        assert_eq!(mismatches[0].fragment_input.ty, vec(4));
+    }
+
+    #[test]
+    fn built_ins_are_skipped() {
+        let vertex = reflect(&interface_module(
+            0,
+            STORAGE_OUTPUT,
+            &[(None, 4), (Some(0), 3)],
+        ))
+        .unwrap();
+        assert_eq!(vertex.outputs.len(), 1);
+        assert_eq!(vertex.outputs[0].location, 0);
+        assert_eq!(vertex.outputs[0].ty, vec(3));
+    }
+
+    fn spec_constant_module() -> Vec<u8> {
+        module(&[
+            entry_point(5, "main", &[]),
+            op(OP_TYPE_INT, &[1, 32, 1]),
+            op(OP_TYPE_FLOAT, &[2, 64]),
+            op(OP_TYPE_BOOL, &[3]),
+            op(OP_DECORATE, &[10, DECORATION_SPEC_ID, 2]),
+            op(OP_DECORATE, &[11, DECORATION_SPEC_ID, 0]),
+            op(OP_DECORATE, &[12, DECORATION_SPEC_ID, 1]),
+            op(OP_SPEC_CONSTANT, &[1, 10, 7]),
+            op(OP_SPEC_CONSTANT, &[2, 11, 0, 0]),
+            op(OP_SPEC_CONSTANT_TRUE, &[3, 12]),
+            // Not decorated, so not something a pipeline can set.
+            op(OP_SPEC_CONSTANT, &[1, 13, 0]),
+        ])
+    }
+
+    #[test]
+    fn spec_constant_ids_and_sizes() {
+        let reflection = reflect(&spec_constant_module()).unwrap();
+        let constants: Vec<(u32, usize)> = reflection
+            .specialization_constants
+            .iter()
+            .map(|constant| (constant.constant_id, constant.size()))
+            .collect();
+        assert_eq!(constants, vec![(0, 8), (1, 4), (2, 4)]);
+        assert_eq!(
+            reflection.specialization_constants[1].ty,
+            ReflectedType::Bool
+        );
+    }
+
+    #[test]
+    fn spec_constant_entries_are_checked() {
+        let reflection = reflect(&spec_constant_module()).unwrap();
+        let entry = |constant_id, offset, size| SpecializationMapEntry {
+            constant_id,
+            offset,
+            size,
+        };
+        assert!(
+            validate_specialization(&reflection, &[entry(0, 0, 8), entry(2, 8, 4)], 12).is_empty()
+        );
+        assert_eq!(
+            validate_specialization(
+                &reflection,
+                &[entry(0, 0, 4), entry(9, 4, 4), entry(1, 8, 4)],
+                8
+            ),
+            vec![
+                SpecializationMismatch::Size {
+                    constant_id: 0,
+                    ty: ReflectedType::Float { width: 64 },
+                    size: 4,
+                },
+                SpecializationMismatch::UnknownConstant { constant_id: 9 },
+                SpecializationMismatch::OutOfBounds {
+                    constant_id: 1,
+                    offset: 8,
+                    size: 4,
+                    struct_size: 8,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn bad_input_is_rejected() {
+        let mut bytes = module(&[]);
+        bytes[0] = 0xef;
+        assert_eq!(
+            reflect(&bytes).unwrap_err(),
+            ReflectError::BadMagic(0x0723_02ef)
+        );
+        assert_eq!(
+            reflect(&bytes[..19]).unwrap_err(),
+            ReflectError::NotWordAligned(19)
+        );
+        assert_eq!(reflect(&bytes[..16]).unwrap_err(), ReflectError::TooShort);
+    }
+
+    #[test]
+    fn truncated_instructions_are_rejected() {
+        let mut bytes = module(&[op(OP_TYPE_FLOAT, &[FLOAT, 32])]);
+        bytes.truncate(bytes.len() - 4);
+        assert_eq!(
+            reflect(&bytes).unwrap_err(),
+            ReflectError::Truncated { word: 5 }
+        );
+        // A word count of zero would never move past the instruction.
+        let bytes = module(&[vec![OP_TYPE_FLOAT]]);
+        assert_eq!(
+            reflect(&bytes).unwrap_err(),
+            ReflectError::Truncated { word: 5 }
+        );
+    }
+
+    #[test]
+    fn big_endian_modules_are_swapped() {
+        let bytes = interface_module(4, STORAGE_INPUT, &[(Some(2), 2)]);
+        let swapped: Vec<u8> = bytes
+            .chunks(4)
+            .flat_map(|word| word.iter().rev().cloned())
+            .collect();
+        let reflection = reflect(&swapped).unwrap();
+        assert_eq!(reflection.inputs[0].location, 2);
+        assert_eq!(reflection.entry_point, Some("main".to_string()));
+    }
+
+    #[test]
+    fn self_nesting_types_are_rejected() {
+        let bytes = module(&[
+            op(OP_TYPE_STRUCT, &[5, 5]),
+            op(OP_TYPE_POINTER, &[6, STORAGE_PUSH_CONSTANT, 5]),
+            op(OP_VARIABLE, &[6, 7, STORAGE_PUSH_CONSTANT]),
+        ]);
+        match reflect(&bytes) {
+            Err(ReflectError::TypeTooDeep { .. }) => (),
+            other => panic!("expected TypeTooDeep, got {:?}", other),
+        }
+        let bytes = module(&[
+            op(OP_TYPE_VECTOR, &[5, 5, 4]),
+            op(OP_DECORATE, &[7, DECORATION_LOCATION, 0]),
+            op(OP_TYPE_POINTER, &[6, STORAGE_INPUT, 5]),
+            op(OP_VARIABLE, &[6, 7, STORAGE_INPUT]),
+        ]);
+        match reflect(&bytes) {
+            Err(ReflectError::TypeTooDeep { .. }) => (),
+            other => panic!("expected TypeTooDeep, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn zero_length_vectors_have_no_format() {
+        assert_eq!(vec(0).format(), None);
+        assert_eq!(vec(3).format(), Some(Format::R32G32B32Sfloat));
+        assert_eq!(vec(5).format(), None);
+    }
+
+    #[test]
+    fn locations_count_columns_and_wide_vectors() {
+        let mat4 = ReflectedType::Matrix {
+            column: Box::new(vec(4)),
+            columns: 4,
+        };
+        let dvec3 = ReflectedType::Vector {
+            component: Box::new(ReflectedType::Float { width: 64 }),
+            count: 3,
+        };
+        assert_eq!(mat4.locations(), 4);
+        assert_eq!(dvec3.locations(), 2);
+        assert_eq!(vec(4).locations(), 1);
+    }
+
+    #[test]
+    fn push_constants_start_at_their_first_member() {
+        let bytes = module(&[
+            op(OP_TYPE_FLOAT, &[FLOAT, 32]),
+            op(OP_TYPE_VECTOR, &[2, FLOAT, 4]),
+            op(OP_TYPE_STRUCT, &[5, FLOAT, 2]),
+            op(OP_MEMBER_DECORATE, &[5, 0, DECORATION_OFFSET, 16]),
+            op(OP_MEMBER_DECORATE, &[5, 1, DECORATION_OFFSET, 32]),
+            op(OP_TYPE_POINTER, &[6, STORAGE_PUSH_CONSTANT, 5]),
+            op(OP_VARIABLE, &[6, 7, STORAGE_PUSH_CONSTANT]),
+        ]);
+        let reflection = reflect(&bytes).unwrap();
+        assert_eq!(
+            reflection.push_constant_ranges,
+            vec![PushConstantRange {
+                name: None,
+                offset: 16,
+                size: 32,
+            }]
+        );
+    }
+
+    #[test]
+    fn each_entry_point_sees_its_own_interface() {
+        let bytes = module(&[
+            entry_point(0, "vs_main", &[30]),
+            entry_point(4, "fs_main", &[31]),
+            op(OP_TYPE_FLOAT, &[FLOAT, 32]),
+            op(OP_TYPE_VECTOR, &[2, FLOAT, 4]),
+            op(OP_TYPE_POINTER, &[3, STORAGE_OUTPUT, 2]),
+            op(OP_TYPE_POINTER, &[4, STORAGE_INPUT, 2]),
+            op(OP_DECORATE, &[30, DECORATION_LOCATION, 0]),
+            op(OP_DECORATE, &[31, DECORATION_LOCATION, 0]),
+            op(OP_VARIABLE, &[3, 30, STORAGE_OUTPUT]),
+            op(OP_VARIABLE, &[4, 31, STORAGE_INPUT]),
+        ]);
+        let vertex = reflect_stage(&bytes, ExecutionModel::Vertex).unwrap();
+        assert_eq!(vertex.entry_point, Some("vs_main".to_string()));
+        assert_eq!((vertex.inputs.len(), vertex.outputs.len()), (0, 1));
+        let fragment = reflect_stage(&bytes, ExecutionModel::Fragment).unwrap();
+        assert_eq!(fragment.entry_point, Some("fs_main".to_string()));
+        assert_eq!((fragment.inputs.len(), fragment.outputs.len()), (1, 0));
+        let geometry = reflect_stage(&bytes, ExecutionModel::Geometry).unwrap();
+        assert_eq!(geometry.execution_model, None);
+        assert_eq!(geometry.entry_points.len(), 2);
+    }
+}
@@ -0,0 +1,49 @@
+use std::sync::Arc;
+use vulkano::device::Device;
+use vulkano::format::Format;
+use vulkano::framebuffer::{Framebuffer, FramebufferAbstract, RenderPassAbstract};
+use vulkano::image::{ImageLayout, SwapchainImage};
+use winit;
+
+use error::VulkanInitError;
+
+/// A single color attachment in the swapchain's format.
+/// Cleared on load, stored, and left ready to present.
+pub fn create_render_pass(
+    device: Arc<Device>,
+    format: Format,
+) -> Result<Arc<RenderPassAbstract + Send + Sync>, VulkanInitError> {
+    let render_pass = single_pass_renderpass!(device,
+        attachments: {
+            color: {
+                load: Clear,
+                store: Store,
+                format: format,
+                samples: 1,
+                initial_layout: ImageLayout::Undefined,
+                final_layout: ImageLayout::PresentSrc,
+            }
+        },
+        pass: {
+            color: [color],
+            depth_stencil: {}
+        }
+    )?;
+    Ok(Arc::new(render_pass))
+}
+
+/// One framebuffer per swapchain image, in the same order as the images.
+pub fn create_framebuffers(
+    render_pass: Arc<RenderPassAbstract + Send + Sync>,
+    images: &[Arc<SwapchainImage<winit::Window>>],
+) -> Result<Vec<Arc<FramebufferAbstract + Send + Sync>>, VulkanInitError> {
+    images
+        .iter()
+        .map(|image| {
+            let framebuffer = Framebuffer::start(render_pass.clone())
+                .add(image.clone())?
+                .build()?;
+            Ok(Arc::new(framebuffer) as Arc<FramebufferAbstract + Send + Sync>)
+        })
+        .collect()
+}
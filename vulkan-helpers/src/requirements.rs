@@ -0,0 +1,83 @@
+use vulkano::device::DeviceExtensions;
+use vulkano::instance::{Features, PhysicalDevice};
+
+/// What a device must have to be picked, and what gets turned on when it happens to have it.
+#[derive(Debug, Clone)]
+pub struct DeviceRequirements {
+    pub required_features: Features,
+    pub optional_features: Features,
+    pub required_extensions: DeviceExtensions,
+    pub optional_extensions: DeviceExtensions,
+}
+
+impl Default for DeviceRequirements {
+    fn default() -> DeviceRequirements {
+        DeviceRequirements {
+            required_features: Features {
+                geometry_shader: true,
+                ..Features::none()
+            },
+            optional_features: Features::none(),
+            required_extensions: DeviceExtensions {
+                khr_swapchain: true,
+                ..DeviceExtensions::none()
+            },
+            optional_extensions: init_vulkan_optional_device_extensions(),
+        }
+    }
+}
+
+impl DeviceRequirements {
+    /// Why `physical_device` can't be used, or `None` when it has everything required.
+    pub fn rejection(&self, physical_device: PhysicalDevice) -> Option<String> {
+        let supported_features = physical_device.supported_features();
+        let supported_extensions = DeviceExtensions::supported_by_device(physical_device);
+        let mut reasons = vec![];
+        if !supported_features.superset_of(&self.required_features) {
+            reasons.push(format!(
+                "missing features {:?}",
+                self.required_features.difference(supported_features)
+            ));
+        }
+        let missing_extensions = self.required_extensions
+            .difference(&supported_extensions);
+        if missing_extensions != DeviceExtensions::none() {
+            reasons.push(format!("missing extensions {:?}", missing_extensions));
+        }
+        if reasons.is_empty() {
+            None
+        } else {
+            Some(reasons.join(", "))
+        }
+    }
+
+    /// Required features plus whichever optional ones `physical_device` supports.
+    pub fn enabled_features(&self, physical_device: PhysicalDevice) -> Features {
+        let supported = physical_device.supported_features();
+        // NOTE: `Features` has no union, so this is
+        // supported ∩ (required ∪ optional) spelled with differences.
+        let unwanted = supported
+            .difference(&self.required_features)
+            .difference(&self.optional_features);
+        supported.difference(&unwanted)
+    }
+
+    /// Required extensions plus whichever optional ones `physical_device` supports.
+    pub fn enabled_extensions(&self, physical_device: PhysicalDevice) -> DeviceExtensions {
+        let supported = DeviceExtensions::supported_by_device(physical_device);
+        self.required_extensions
+            .union(&supported.intersection(&self.optional_extensions))
+    }
+}
+
+#[cfg(feature = "vk_debug")]
+fn init_vulkan_optional_device_extensions() -> DeviceExtensions {
+    DeviceExtensions {
+        ext_debug_marker: true,
+        ..DeviceExtensions::none()
+    }
+}
+#[cfg(not(feature = "vk_debug"))]
+fn init_vulkan_optional_device_extensions() -> DeviceExtensions {
+    DeviceExtensions::none()
+}
@@ -0,0 +1,286 @@
+use std::env;
+use std::fmt;
+use std::sync::Arc;
+use vulkano::instance::{Features, Instance, PhysicalDevice, PhysicalDeviceType, Version};
+use vulkano::swapchain::Surface;
+use winit;
+
+use device::check_device_support;
+use requirements::DeviceRequirements;
+
+/// Environment variable checked for a `DeviceSelector` when `--device` isn't passed.
+pub const DEVICE_ENV_VAR: &str = "VKTUT_DEVICE";
+pub const DEVICE_ARG: &str = "--device";
+
+/// Forces which physical device gets picked, bypassing the ranking.
+///
+/// Parsed from strings like `1` (index), `10de:1b80` (vendor:device in hex),
+/// `0x10de` (vendor only) or anything else as a case insensitive name substring, e.g. `llvmpipe`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeviceSelector {
+    Index(usize),
+    VendorDevice { vendor: u32, device: Option<u32> },
+    Name(String),
+}
+
+impl DeviceSelector {
+    pub fn parse(selector: &str) -> DeviceSelector {
+        let selector = selector.trim();
+        if let Ok(index) = selector.parse::<usize>() {
+            return DeviceSelector::Index(index);
+        }
+        let mut ids = selector.splitn(2, ':');
+        match (ids.next(), ids.next()) {
+            (Some(vendor), Some(device)) => {
+                if let (Some(vendor), Some(device)) = (parse_hex(vendor), parse_hex(device)) {
+                    return DeviceSelector::VendorDevice {
+                        vendor,
+                        device: Some(device),
+                    };
+                }
+            }
+            (Some(vendor), None) if vendor.starts_with("0x") || vendor.starts_with("0X") => {
+                if let Some(vendor) = parse_hex(vendor) {
+                    return DeviceSelector::VendorDevice {
+                        vendor,
+                        device: None,
+                    };
+                }
+            }
+            _ => (),
+        }
+        DeviceSelector::Name(selector.to_lowercase())
+    }
+
+    pub fn matches(&self, device: &RankedDevice) -> bool {
+        match *self {
+            DeviceSelector::Index(index) => device.index == index,
+            DeviceSelector::VendorDevice {
+                vendor,
+                device: device_id,
+            } => device.vendor_id == vendor && device_id.map_or(true, |id| device.device_id == id),
+            DeviceSelector::Name(ref name) => device.name.to_lowercase().contains(name.as_str()),
+        }
+    }
+
+    /// Reads `--device <selector>` or `--device=<selector>` from the command line,
+    /// then falls back to `VKTUT_DEVICE`.
+    pub fn from_env_or_args() -> Option<DeviceSelector> {
+        DeviceSelector::from_args(env::args().skip(1)).or_else(DeviceSelector::from_env)
+    }
+
+    pub fn from_env() -> Option<DeviceSelector> {
+        env::var(DEVICE_ENV_VAR)
+            .ok()
+            .filter(|selector| !selector.trim().is_empty())
+            .map(|selector| DeviceSelector::parse(&selector))
+    }
+
+    pub fn from_args<I: Iterator<Item = String>>(mut args: I) -> Option<DeviceSelector> {
+        let prefix = format!("{}=", DEVICE_ARG);
+        while let Some(arg) = args.next() {
+            if arg == DEVICE_ARG {
+                return args.next().map(|selector| DeviceSelector::parse(&selector));
+            }
+            if arg.starts_with(&prefix) {
+                return Some(DeviceSelector::parse(&arg[prefix.len()..]));
+            }
+        }
+        None
+    }
+}
+
+impl fmt::Display for DeviceSelector {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DeviceSelector::Index(index) => write!(f, "index {}", index),
+            DeviceSelector::VendorDevice {
+                vendor,
+                device: Some(device),
+            } => write!(f, "id {:04x}:{:04x}", vendor, device),
+            DeviceSelector::VendorDevice { vendor, device: None } => {
+                write!(f, "vendor {:04x}", vendor)
+            }
+            DeviceSelector::Name(ref name) => write!(f, "name containing \"{}\"", name),
+        }
+    }
+}
+
+fn parse_hex(id: &str) -> Option<u32> {
+    let id = id.trim_left_matches("0x").trim_left_matches("0X");
+    u32::from_str_radix(id, 16).ok()
+}
+
+/// A physical device along with how much we'd like to use it.
+#[derive(Debug, Clone)]
+pub struct RankedDevice {
+    pub index: usize,
+    pub name: String,
+    pub vendor_id: u32,
+    pub device_id: u32,
+    pub ty: PhysicalDeviceType,
+    pub api_version: Version,
+    /// Why the device can't be used, `None` when it meets the requirements and can present to the surface.
+    pub rejection: Option<String>,
+    pub score: u32,
+}
+
+impl RankedDevice {
+    pub fn suitable(&self) -> bool {
+        self.rejection.is_none()
+    }
+}
+
+/// Higher is better. The device type dominates, the rest only breaks ties between similar devices.
+pub fn score_device(physical_device: PhysicalDevice, optional_features: &Features) -> u32 {
+    let type_score = match physical_device.ty() {
+        PhysicalDeviceType::DiscreteGpu => 10_000,
+        PhysicalDeviceType::IntegratedGpu => 5_000,
+        PhysicalDeviceType::VirtualGpu => 2_500,
+        PhysicalDeviceType::Cpu => 1_000,
+        PhysicalDeviceType::Other => 0,
+    };
+    let api_version = physical_device.api_version();
+    let version_score = u32::from(api_version.minor) * 100;
+    let limits_score = physical_device.limits().max_image_dimension_2d() / 1024;
+    let features_score = if physical_device
+        .supported_features()
+        .superset_of(optional_features)
+    {
+        500
+    } else {
+        0
+    };
+    type_score + version_score + limits_score + features_score
+}
+
+/// Scores every physical device, best first, and prints the table.
+/// Devices that can't present to `surface` are ranked below every usable one.
+pub fn rank_devices(
+    instance: &Arc<Instance>,
+    surface: &Surface<winit::Window>,
+    requirements: &DeviceRequirements,
+) -> Vec<RankedDevice> {
+    let mut ranked: Vec<RankedDevice> = PhysicalDevice::enumerate(instance)
+        .map(|physical_device| RankedDevice {
+            index: physical_device.index(),
+            name: physical_device.name(),
+            vendor_id: physical_device.pci_vendor_id(),
+            device_id: physical_device.pci_device_id(),
+            ty: physical_device.ty(),
+            api_version: physical_device.api_version(),
+            rejection: check_device_support(physical_device, surface, requirements),
+            score: score_device(physical_device, &requirements.optional_features),
+        })
+        .collect();
+    ranked.sort_by(|a, b| b.suitable().cmp(&a.suitable()).then(b.score.cmp(&a.score)));
+    println!("Ranked PhysicalDevices:");
+    for device in &ranked {
+        if device.suitable() {
+            print!("  ✔️ ");
+        } else {
+            print!("  ❌ ");
+        }
+        println!("score: {:>6}, {}", device.score, device);
+        if let Some(ref reason) = device.rejection {
+            println!("    rejected: {}", reason);
+        }
+    }
+    ranked
+}
+
+impl fmt::Display for RankedDevice {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "index: {}, {} ({:04x}:{:04x}), type: {:?}, supports: {}",
+            self.index,
+            self.name,
+            self.vendor_id,
+            self.device_id,
+            self.ty,
+            self.api_version,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_index() {
+        assert_eq!(DeviceSelector::parse("1"), DeviceSelector::Index(1));
+        assert_eq!(DeviceSelector::parse(" 0 "), DeviceSelector::Index(0));
+    }
+
+    #[test]
+    fn parses_vendor_and_device() {
+        assert_eq!(
+            DeviceSelector::parse("10de:1b80"),
+            DeviceSelector::VendorDevice {
+                vendor: 0x10de,
+                device: Some(0x1b80),
+            }
+        );
+        assert_eq!(
+            DeviceSelector::parse("0x1002:0X67df"),
+            DeviceSelector::VendorDevice {
+                vendor: 0x1002,
+                device: Some(0x67df),
+            }
+        );
+    }
+
+    #[test]
+    fn parses_vendor_only_with_prefix() {
+        assert_eq!(
+            DeviceSelector::parse("0x10de"),
+            DeviceSelector::VendorDevice {
+                vendor: 0x10de,
+                device: None,
+            }
+        );
+        // Without `0x` a bare hex number could be a name, and one that's all digits is an index.
+        assert_eq!(
+            DeviceSelector::parse("10de"),
+            DeviceSelector::Name("10de".to_string())
+        );
+    }
+
+    #[test]
+    fn anything_else_is_a_lowercase_name() {
+        assert_eq!(
+            DeviceSelector::parse("LLVMpipe"),
+            DeviceSelector::Name("llvmpipe".to_string())
+        );
+        assert_eq!(
+            DeviceSelector::parse("gtx:1080"),
+            DeviceSelector::Name("gtx:1080".to_string())
+        );
+        assert_eq!(
+            DeviceSelector::parse("0xzz"),
+            DeviceSelector::Name("0xzz".to_string())
+        );
+    }
+
+    #[test]
+    fn reads_device_argument() {
+        let args = |args: &[&str]| {
+            args.iter()
+                .map(|arg| arg.to_string())
+                .collect::<Vec<_>>()
+                .into_iter()
+        };
+        assert_eq!(
+            DeviceSelector::from_args(args(&["--verbose", "--device", "2"])),
+            Some(DeviceSelector::Index(2))
+        );
+        assert_eq!(
+            DeviceSelector::from_args(args(&["--device=intel"])),
+            Some(DeviceSelector::Name("intel".to_string()))
+        );
+        assert_eq!(DeviceSelector::from_args(args(&["--device"])), None);
+        assert_eq!(DeviceSelector::from_args(args(&[])), None);
+    }
+}
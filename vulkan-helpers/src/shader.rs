@@ -0,0 +1,241 @@
+use glsl_preprocess::{preprocess, Preprocessed};
+use glsl_to_spirv;
+use std;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use vulkano::device::Device;
+use vulkano::pipeline::shader::ShaderModule;
+use vulkano_shaders::ShaderType;
+
+use cache::{CacheKey, ShaderCache};
+use reflect::{reflect_stage, ExecutionModel, ShaderReflection};
+use shader_error::ShaderError;
+
+/// Where a shader stage comes from.
+#[derive(Debug, Clone)]
+pub enum ShaderSource {
+    /// A file relative to the working directory. GLSL gets compiled when the shader is loaded,
+    /// SPIR-V from other toolchains is loaded as is if the file ends in `.spv`.
+    File(String),
+    /// SPIR-V compiled ahead of time, like the bytes a build script embeds.
+    /// `name` is only used for messages.
+    Spirv { name: String, bytes: Vec<u8> },
+}
+
+impl ShaderSource {
+    pub fn name(&self) -> &str {
+        match *self {
+            ShaderSource::File(ref path) => path,
+            ShaderSource::Spirv { ref name, .. } => name,
+        }
+    }
+}
+
+/// How GLSL gets preprocessed before glslang sees it.
+#[derive(Debug, Clone, Default)]
+pub struct CompileOptions {
+    /// Where `#include <file>` looks, and `#include "file"` when it's not next to the includer.
+    /// Defaults to the directory of the shader being compiled, `shaders/` for ours.
+    pub include_root: Option<PathBuf>,
+    /// Injected as `#define NAME VALUE` right after `#version`.
+    pub defines: Vec<(String, String)>,
+}
+
+impl CompileOptions {
+    pub fn new() -> CompileOptions {
+        CompileOptions::default()
+    }
+
+    pub fn with_include_root<P: Into<PathBuf>>(mut self, include_root: P) -> CompileOptions {
+        self.include_root = Some(include_root.into());
+        self
+    }
+
+    pub fn with_define<N, V>(mut self, name: N, value: V) -> CompileOptions
+    where
+        N: Into<String>,
+        V: Into<String>,
+    {
+        self.defines.push((name.into(), value.into()));
+        self
+    }
+}
+
+/// A shader module together with what reflecting its SPIR-V found.
+#[derive(Clone)]
+pub struct LoadedShader {
+    pub module: Arc<ShaderModule>,
+    pub reflection: ShaderReflection,
+    /// The file it was loaded from, `None` for SPIR-V handed over as bytes.
+    pub source_path: Option<PathBuf>,
+    /// Canonicalized paths of every file it `#include`d, so editing one can reload this shader.
+    pub includes: Vec<PathBuf>,
+}
+
+pub fn load_shader(
+    device: Arc<Device>,
+    source: &ShaderSource,
+    shader_type: ShaderType,
+    options: &CompileOptions,
+    cache: Option<&ShaderCache>,
+) -> Result<LoadedShader, ShaderError> {
+    match *source {
+        ShaderSource::File(ref path) => create_shader(device, path, shader_type, options, cache),
+        ShaderSource::Spirv {
+            ref name,
+            ref bytes,
+        } => {
+            print!("Loading {:?} shader from {} ", shader_type, name);
+            let shader = create_loaded_shader(device, name, bytes, &shader_type)?;
+            println!("✔️");
+            Ok(shader)
+        }
+    }
+}
+
+/// Compiles the GLSL at `path`, or loads it from `cache` if this exact source was compiled before.
+/// A `.spv` path is loaded without compiling, after checking it's SPIR-V for the right stage.
+pub fn create_shader(
+    device: Arc<Device>,
+    path: &str,
+    shader_type: ShaderType,
+    options: &CompileOptions,
+    cache: Option<&ShaderCache>,
+) -> Result<LoadedShader, ShaderError> {
+    if Path::new(path).extension().map_or(false, |extension| extension == "spv") {
+        print!("Loading {:?} shader from {} ", shader_type, path);
+        let spirv_bytes = std::fs::read(path).map_err(|error| {
+            println!("❌");
+            ShaderError::Read {
+                path: path.to_string(),
+                error,
+            }
+        })?;
+        let mut shader = create_loaded_shader(device, path, &spirv_bytes, &shader_type)?;
+        shader.source_path = Some(PathBuf::from(path));
+        println!("✔️");
+        return Ok(shader);
+    }
+    let source = std::fs::read_to_string(path).map_err(|error| ShaderError::Read {
+        path: path.to_string(),
+        error,
+    })?;
+    let preprocessed = preprocess_glsl(path, &source, options)?;
+    let mut key = CacheKey::new(path, &shader_type);
+    for &(ref name, ref value) in &options.defines {
+        key = key.with_variant(&format!("{}={}", name, value));
+    }
+    // The expanded source has every include pasted in, so it covers them too.
+    let key = key.with_source(&preprocessed.source);
+    let cached = cache.and_then(|cache| cache.get(&key));
+    let spirv_bytes = match cached {
+        Some(spirv_bytes) => {
+            print!("Loading cached {:?} shader for {} ", shader_type, path);
+            spirv_bytes
+        }
+        None => {
+            print!("Compiling {:?} shader from {} ", shader_type, path);
+            let spirv_bytes = compile_glsl(path, &preprocessed, shader_type.clone())?;
+            if let Some(cache) = cache {
+                // A cache that can't be written to only costs a recompile next time.
+                if let Err(err) = cache.put(&key, &spirv_bytes) {
+                    print!("(not cached: {}) ", err);
+                }
+            }
+            spirv_bytes
+        }
+    };
+    let mut shader = create_loaded_shader(device, path, &spirv_bytes, &shader_type)?;
+    shader.source_path = Some(PathBuf::from(path));
+    shader.includes = preprocessed.includes;
+    println!("✔️");
+    Ok(shader)
+}
+
+fn preprocess_glsl(
+    path: &str,
+    source: &str,
+    options: &CompileOptions,
+) -> Result<Preprocessed, ShaderError> {
+    let path_buf = PathBuf::from(path);
+    let include_root = match options.include_root {
+        Some(ref include_root) => include_root.clone(),
+        None => path_buf.parent().map(Path::to_path_buf).unwrap_or_default(),
+    };
+    preprocess(&path_buf, source, &include_root, &options.defines).map_err(|error| {
+        ShaderError::Include {
+            path: path.to_string(),
+            error,
+        }
+    })
+}
+
+fn compile_glsl(
+    path: &str,
+    preprocessed: &Preprocessed,
+    shader_type: ShaderType,
+) -> Result<Vec<u8>, ShaderError> {
+    let mut shader = glsl_to_spirv::compile(&preprocessed.source, shader_type).map_err(|output| {
+        println!("❌");
+        ShaderError::compile(path, preprocessed, output)
+    })?;
+    let mut spirv_bytes: Vec<u8> = Vec::new();
+    shader
+        .read_to_end(&mut spirv_bytes)
+        .map_err(|error| ShaderError::Read {
+            path: path.to_string(),
+            error,
+        })?;
+    Ok(spirv_bytes)
+}
+
+/// Checks the SPIR-V is well formed and has an entry point for `shader_type` before handing it to the driver,
+/// which would otherwise take a bad module without complaint and fail somewhere later.
+fn create_loaded_shader(
+    device: Arc<Device>,
+    name: &str,
+    spirv_bytes: &[u8],
+    shader_type: &ShaderType,
+) -> Result<LoadedShader, ShaderError> {
+    let expected = ExecutionModel::for_shader_type(shader_type);
+    let reflection = reflect_stage(spirv_bytes, expected).map_err(|error| {
+        println!("❌");
+        ShaderError::Reflect {
+            path: name.to_string(),
+            error,
+        }
+    })?;
+    if reflection.execution_model.is_none() {
+        println!("❌");
+        return Err(ShaderError::Stage {
+            path: name.to_string(),
+            expected,
+            found: reflection
+                .entry_points
+                .iter()
+                .map(|entry_point| entry_point.execution_model)
+                .collect(),
+        });
+    }
+    let module = create_shader_module(device, name, spirv_bytes)?;
+    Ok(LoadedShader {
+        module,
+        reflection,
+        source_path: None,
+        includes: vec![],
+    })
+}
+
+pub fn create_shader_module(
+    device: Arc<Device>,
+    name: &str,
+    spirv_bytes: &[u8],
+) -> Result<Arc<ShaderModule>, ShaderError> {
+    unsafe { ShaderModule::new(device, spirv_bytes) }.map_err(|error| {
+        ShaderError::Module {
+            path: name.to_string(),
+            error,
+        }
+    })
+}
@@ -0,0 +1,189 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+use vulkano::OomError;
+
+use glsl_preprocess::{Diagnostic, IncludeError, Preprocessed};
+use reflect::{ExecutionModel, ReflectError};
+use variants::MAX_FEATURES;
+
+/// Why a shader stage couldn't be loaded.
+#[derive(Debug)]
+pub enum ShaderError {
+    Read {
+        path: String,
+        error: io::Error,
+    },
+    /// `source` is what glslang saw, with includes expanded and defines injected.
+    Compile {
+        path: String,
+        source: String,
+        diagnostics: Vec<Diagnostic>,
+        output: String,
+    },
+    Include {
+        path: String,
+        error: IncludeError,
+    },
+    Module {
+        path: String,
+        error: OomError,
+    },
+    Reflect {
+        path: String,
+        error: ReflectError,
+    },
+    /// A `ShaderVariants` was asked for a feature it doesn't declare.
+    UnknownFeature {
+        path: String,
+        feature: String,
+        known: Vec<String>,
+    },
+    /// A `ShaderVariants` declared the same feature twice.
+    DuplicateFeature {
+        path: String,
+        feature: String,
+    },
+    /// A `ShaderVariants` declared more features than fit in a `VariantKey`.
+    TooManyFeatures {
+        path: String,
+        count: usize,
+    },
+    /// The SPIR-V has no entry point for the stage it's being loaded as.
+    /// `found` are the stages it does have entry points for.
+    Stage {
+        path: String,
+        expected: ExecutionModel,
+        found: Vec<ExecutionModel>,
+    },
+}
+
+impl ShaderError {
+    /// Points each diagnostic back at the file and line it came from before includes were expanded.
+    pub fn compile(path: &str, preprocessed: &Preprocessed, output: String) -> ShaderError {
+        let diagnostics = Diagnostic::parse_preprocessed(path, preprocessed, &output);
+        ShaderError::Compile {
+            path: path.to_string(),
+            source: preprocessed.source.clone(),
+            diagnostics,
+            output,
+        }
+    }
+
+    pub fn path(&self) -> &str {
+        match *self {
+            ShaderError::Read { ref path, .. } => path,
+            ShaderError::Compile { ref path, .. } => path,
+            ShaderError::Include { ref path, .. } => path,
+            ShaderError::Module { ref path, .. } => path,
+            ShaderError::Reflect { ref path, .. } => path,
+            ShaderError::UnknownFeature { ref path, .. } => path,
+            ShaderError::DuplicateFeature { ref path, .. } => path,
+            ShaderError::TooManyFeatures { ref path, .. } => path,
+            ShaderError::Stage { ref path, .. } => path,
+        }
+    }
+
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        match *self {
+            ShaderError::Compile {
+                ref diagnostics, ..
+            } => diagnostics,
+            _ => &[],
+        }
+    }
+}
+
+impl fmt::Display for ShaderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ShaderError::Read {
+                ref path,
+                ref error,
+            } => write!(f, "could not read shader file {}: {}", path, error),
+            ShaderError::Compile {
+                ref path,
+                ref diagnostics,
+                ref output,
+                ..
+            } => {
+                writeln!(f, "shader compile failed for {}", path)?;
+                if diagnostics.is_empty() {
+                    return write!(f, "{}", output.trim());
+                }
+                for diagnostic in diagnostics {
+                    write!(f, "{}", diagnostic.render())?;
+                }
+                Ok(())
+            }
+            ShaderError::Include {
+                ref path,
+                ref error,
+            } => write!(f, "could not expand includes for {}: {}", path, error),
+            ShaderError::Module {
+                ref path,
+                ref error,
+            } => write!(f, "failed to create shader module for {}: {}", path, error),
+            ShaderError::Reflect {
+                ref path,
+                ref error,
+            } => write!(f, "could not reflect SPIR-V for {}: {}", path, error),
+            ShaderError::UnknownFeature {
+                ref path,
+                ref feature,
+                ref known,
+            } => write!(
+                f,
+                "{} has no feature {}, it declares {}",
+                path,
+                feature,
+                if known.is_empty() {
+                    String::from("none")
+                } else {
+                    known.join(", ")
+                }
+            ),
+            ShaderError::DuplicateFeature {
+                ref path,
+                ref feature,
+            } => write!(f, "{} declares feature {} more than once", path, feature),
+            ShaderError::TooManyFeatures { ref path, count } => write!(
+                f,
+                "{} declares {} features, at most {} fit in a variant key",
+                path, count, MAX_FEATURES
+            ),
+            ShaderError::Stage {
+                ref path,
+                expected,
+                ref found,
+            } if found.is_empty() => {
+                write!(f, "{} has no entry point, expected a {:?} shader", path, expected)
+            }
+            ShaderError::Stage {
+                ref path,
+                expected,
+                ref found,
+            } => write!(
+                f,
+                "{} only has entry points for {:?}, expected a {:?} shader",
+                path, found, expected
+            ),
+        }
+    }
+}
+
+impl Error for ShaderError {
+    fn source(&self) -> Option<&(Error + 'static)> {
+        match *self {
+            ShaderError::Read { ref error, .. } => Some(error),
+            ShaderError::Compile { .. } => None,
+            ShaderError::Include { ref error, .. } => Some(error),
+            ShaderError::Module { ref error, .. } => Some(error),
+            ShaderError::Reflect { ref error, .. } => Some(error),
+            ShaderError::UnknownFeature { .. } => None,
+            ShaderError::DuplicateFeature { .. } => None,
+            ShaderError::TooManyFeatures { .. } => None,
+            ShaderError::Stage { .. } => None,
+        }
+    }
+}
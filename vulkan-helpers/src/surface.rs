@@ -0,0 +1,46 @@
+use std::sync::Arc;
+use vulkano::instance::Instance;
+use vulkano::swapchain::Surface;
+use vulkano_win::VkSurfaceBuild;
+use winit;
+
+use error::VulkanInitError;
+
+pub fn init_events_loop() -> winit::EventsLoop {
+    winit::EventsLoop::new()
+}
+
+pub fn init_window(dimensions: [u32; 2], title: &str) -> winit::WindowBuilder {
+    winit::WindowBuilder::new()
+        .with_dimensions(dimensions[0], dimensions[1])
+        .with_title(title)
+}
+
+pub fn init_surface(
+    events_loop: &winit::EventsLoop,
+    instance: Arc<Instance>,
+    window: winit::WindowBuilder,
+) -> Result<Arc<Surface<winit::Window>>, VulkanInitError> {
+    let surface = window.build_vk_surface(events_loop, instance)?;
+    Ok(surface)
+}
+
+/// The window's drawable size in physical pixels, `None` once the window is gone.
+pub fn framebuffer_dimensions(window: &winit::Window) -> Option<[u32; 2]> {
+    let (width, height) = window.get_inner_size()?;
+    let scale = hidpi_scale(window);
+    Some([
+        (width as f32 * scale).round() as u32,
+        (height as f32 * scale).round() as u32,
+    ])
+}
+
+//INFO (danny): winit reports the inner size in points on macOS and in pixels everywhere else.
+#[cfg(target_os = "macos")]
+fn hidpi_scale(window: &winit::Window) -> f32 {
+    window.hidpi_factor()
+}
+#[cfg(not(target_os = "macos"))]
+fn hidpi_scale(_window: &winit::Window) -> f32 {
+    1.0
+}
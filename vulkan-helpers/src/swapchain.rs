@@ -0,0 +1,298 @@
+use std::sync::Arc;
+use vulkano::device::Device;
+use vulkano::format::Format;
+use vulkano::image::SwapchainImage;
+use vulkano::swapchain::{Capabilities, ColorSpace, PresentMode, Surface, SurfaceTransform,
+                         Swapchain};
+use vulkano::sync::SharingMode;
+use winit;
+
+use error::VulkanInitError;
+use surface::framebuffer_dimensions;
+
+pub type SurfaceFormat = (Format, ColorSpace);
+
+/// How frames are paced, turned into a list of present modes to try.
+/// Every list ends in `Fifo` since that's the only mode the spec guarantees.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PresentModePolicy {
+    /// Wait for vertical blank, never tears.
+    Vsync,
+    /// Like `Vsync`, but tears instead of stuttering when a frame comes in late.
+    AdaptiveVsync,
+    /// Replace the queued image with the newest one, no tearing and less latency.
+    LowLatency,
+    /// Present right away, may tear.
+    Uncapped,
+}
+
+impl PresentModePolicy {
+    pub fn from_vsync(vsync: bool) -> PresentModePolicy {
+        if vsync {
+            PresentModePolicy::Vsync
+        } else {
+            PresentModePolicy::Uncapped
+        }
+    }
+
+    pub fn present_modes(&self) -> Vec<PresentMode> {
+        match *self {
+            PresentModePolicy::Vsync => vec![PresentMode::Fifo],
+            PresentModePolicy::AdaptiveVsync => vec![PresentMode::Relaxed, PresentMode::Fifo],
+            PresentModePolicy::LowLatency => vec![PresentMode::Mailbox, PresentMode::Fifo],
+            PresentModePolicy::Uncapped => vec![
+                PresentMode::Immediate,
+                PresentMode::Mailbox,
+                PresentMode::Fifo,
+            ],
+        }
+    }
+}
+
+/// How many images to ask the swapchain for, always clamped to what the surface allows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageCountPolicy {
+    /// One more than the minimum, so we aren't stuck waiting on the driver to release an image.
+    MinPlusOne,
+    DoubleBuffering,
+    TripleBuffering,
+    Exact(u32),
+}
+
+impl Default for ImageCountPolicy {
+    fn default() -> ImageCountPolicy {
+        ImageCountPolicy::MinPlusOne
+    }
+}
+
+/// A freshly built swapchain along with what was picked for it.
+pub struct SwapchainState {
+    pub swapchain: Arc<Swapchain<winit::Window>>,
+    pub images: Vec<Arc<SwapchainImage<winit::Window>>>,
+    pub surface_format: SurfaceFormat,
+    pub present_mode: PresentMode,
+}
+
+/// What the caller would like the swapchain to look like.
+/// Preferences are tried in order and quietly skipped when the surface doesn't support them.
+#[derive(Debug, Clone)]
+pub struct SwapchainConfig {
+    pub present_modes: Vec<PresentMode>,
+    pub formats: Vec<SurfaceFormat>,
+    pub image_count: ImageCountPolicy,
+}
+
+impl Default for SwapchainConfig {
+    fn default() -> SwapchainConfig {
+        SwapchainConfig {
+            present_modes: PresentModePolicy::Vsync.present_modes(),
+            formats: vec![(Format::B8G8R8A8Srgb, ColorSpace::SrgbNonLinear)],
+            image_count: ImageCountPolicy::default(),
+        }
+    }
+}
+
+/// Passing the `old_swapchain` lets the driver reuse its resources when rebuilding.
+pub fn init_swapchain(
+    device: Arc<Device>,
+    surface: Arc<Surface<winit::Window>>,
+    sharing: SharingMode,
+    config: &SwapchainConfig,
+    old_swapchain: Option<&Arc<Swapchain<winit::Window>>>,
+) -> Result<SwapchainState, VulkanInitError> {
+    let caps = surface.capabilities(device.physical_device())?;
+    let dimensions = choose_extent(&caps, framebuffer_dimensions(surface.window()));
+    let alpha = caps.supported_composite_alpha
+        .iter()
+        .next()
+        .ok_or(VulkanInitError::NoSupportedCompositeAlpha)?;
+    let surface_format = choose_surface_format(&caps, &config.formats)?;
+    let present_mode = choose_present_mode(&caps, &config.present_modes);
+    let image_count = choose_image_count(&caps, config.image_count);
+    let (swapchain, images) = Swapchain::new(
+        device.clone(),
+        surface.clone(),
+        image_count,
+        surface_format.0,
+        dimensions,
+        1,
+        caps.supported_usage_flags,
+        sharing,
+        SurfaceTransform::Identity,
+        alpha,
+        present_mode,
+        true,
+        old_swapchain,
+    )?;
+    Ok(SwapchainState {
+        swapchain,
+        images,
+        surface_format,
+        present_mode,
+    })
+}
+
+/// Takes the first preferred format the surface supports.
+/// Otherwise falls back to the first supported `SrgbNonLinear` format, then to the first supported format.
+pub fn choose_surface_format(
+    caps: &Capabilities,
+    preferred: &[SurfaceFormat],
+) -> Result<SurfaceFormat, VulkanInitError> {
+    let supported = &caps.supported_formats;
+    let surface_format = preferred
+        .iter()
+        .find(|format| supported.contains(format))
+        .or_else(|| {
+            supported
+                .iter()
+                .find(|&&(_, color_space)| color_space == ColorSpace::SrgbNonLinear)
+        })
+        .or_else(|| supported.first())
+        .cloned()
+        .ok_or(VulkanInitError::NoSupportedFormat)?;
+    println!("Surface Format:");
+    for format in supported {
+        if *format == surface_format {
+            print!("  ✔️ ");
+        } else {
+            print!("  ❌ ");
+        }
+        println!("{:?}, {:?}", format.0, format.1);
+    }
+    Ok(surface_format)
+}
+
+/// The surface's current extent when it has one.
+/// Some surfaces, like Wayland's, leave it up to us, so the window size is clamped to what's allowed.
+pub fn choose_extent(caps: &Capabilities, window_dimensions: Option<[u32; 2]>) -> [u32; 2] {
+    extent_within(
+        caps.current_extent,
+        caps.min_image_extent,
+        caps.max_image_extent,
+        window_dimensions,
+    )
+}
+
+fn extent_within(
+    current: Option<[u32; 2]>,
+    min: [u32; 2],
+    max: [u32; 2],
+    window_dimensions: Option<[u32; 2]>,
+) -> [u32; 2] {
+    if let Some(extent) = current {
+        return extent;
+    }
+    let dimensions = window_dimensions.unwrap_or(min);
+    [
+        clamp(dimensions[0], min[0], max[0]),
+        clamp(dimensions[1], min[1], max[1]),
+    ]
+}
+
+fn clamp(value: u32, min: u32, max: u32) -> u32 {
+    value.max(min).min(max)
+}
+
+/// Takes the first preferred present mode the surface supports, falling back to `Fifo`.
+pub fn choose_present_mode(caps: &Capabilities, preferred: &[PresentMode]) -> PresentMode {
+    let present_mode = preferred
+        .iter()
+        .find(|&&mode| caps.present_modes.supports(mode))
+        .cloned()
+        .unwrap_or(PresentMode::Fifo);
+    println!("Present Mode:");
+    for mode in caps.present_modes.iter() {
+        if mode == present_mode {
+            print!("  ✔️ ");
+        } else {
+            print!("  ❌ ");
+        }
+        println!("{:?}", mode);
+    }
+    present_mode
+}
+
+pub fn choose_image_count(caps: &Capabilities, policy: ImageCountPolicy) -> u32 {
+    let count = image_count_within(caps.min_image_count, caps.max_image_count, policy);
+    println!(
+        "Swapchain Images: {} ({:?}, min: {}, max: {:?})",
+        count, policy, caps.min_image_count, caps.max_image_count,
+    );
+    count
+}
+
+/// `max` is `None` when the surface has no upper limit.
+fn image_count_within(min: u32, max: Option<u32>, policy: ImageCountPolicy) -> u32 {
+    let requested = match policy {
+        ImageCountPolicy::MinPlusOne => min + 1,
+        ImageCountPolicy::DoubleBuffering => 2,
+        ImageCountPolicy::TripleBuffering => 3,
+        ImageCountPolicy::Exact(count) => count,
+    };
+    let count = requested.max(min);
+    match max {
+        Some(max) => count.min(max),
+        None => count,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn current_extent_wins() {
+        let extent = extent_within(Some([800, 600]), [1, 1], [4096, 4096], Some([1024, 768]));
+        assert_eq!(extent, [800, 600]);
+    }
+
+    #[test]
+    fn window_size_is_clamped_without_current_extent() {
+        assert_eq!(
+            extent_within(None, [1, 1], [4096, 4096], Some([1024, 768])),
+            [1024, 768]
+        );
+        assert_eq!(
+            extent_within(None, [64, 64], [2048, 2048], Some([8, 4000])),
+            [64, 2048]
+        );
+        assert_eq!(extent_within(None, [64, 32], [2048, 2048], None), [64, 32]);
+    }
+
+    #[test]
+    fn image_count_follows_policy() {
+        assert_eq!(
+            image_count_within(2, Some(8), ImageCountPolicy::MinPlusOne),
+            3
+        );
+        assert_eq!(
+            image_count_within(2, Some(8), ImageCountPolicy::DoubleBuffering),
+            2
+        );
+        assert_eq!(
+            image_count_within(2, Some(8), ImageCountPolicy::TripleBuffering),
+            3
+        );
+        assert_eq!(image_count_within(2, None, ImageCountPolicy::Exact(6)), 6);
+    }
+
+    #[test]
+    fn image_count_is_clamped_to_surface_limits() {
+        assert_eq!(
+            image_count_within(3, Some(8), ImageCountPolicy::DoubleBuffering),
+            3
+        );
+        assert_eq!(
+            image_count_within(1, Some(2), ImageCountPolicy::TripleBuffering),
+            2
+        );
+        assert_eq!(
+            image_count_within(2, Some(3), ImageCountPolicy::Exact(0)),
+            2
+        );
+        assert_eq!(
+            image_count_within(2, None, ImageCountPolicy::Exact(100)),
+            100
+        );
+    }
+}
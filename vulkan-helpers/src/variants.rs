@@ -0,0 +1,160 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use vulkano::device::Device;
+use vulkano_shaders::ShaderType;
+
+use cache::ShaderCache;
+use shader::{create_shader, CompileOptions, LoadedShader};
+use shader_error::ShaderError;
+
+/// The most features one shader can have, one per bit of a `VariantKey`.
+pub const MAX_FEATURES: usize = 64;
+
+/// Which of a shader's features are turned on, one bit per feature in the order they were declared.
+/// Only `ShaderVariants::key` makes them, so every key names features the shader has.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct VariantKey(u64);
+
+impl VariantKey {
+    pub fn is_enabled(&self, feature: usize) -> bool {
+        feature < MAX_FEATURES && self.0 & (1 << feature) != 0
+    }
+}
+
+/// One GLSL file that can be compiled with any combination of its feature toggles,
+/// so a single `shader.frag` can serve many materials.
+///
+/// Each enabled feature becomes `#define FEATURE 1`, disabled ones are left undefined for `#ifdef`.
+/// Variants are compiled the first time they're asked for and kept until the file changes.
+pub struct ShaderVariants {
+    path: String,
+    shader_type: ShaderType,
+    features: Vec<String>,
+    options: CompileOptions,
+    compiled: HashMap<VariantKey, LoadedShader>,
+}
+
+impl ShaderVariants {
+    pub fn new<P: Into<String>>(path: P, shader_type: ShaderType) -> ShaderVariants {
+        ShaderVariants {
+            path: path.into(),
+            shader_type,
+            features: vec![],
+            options: CompileOptions::default(),
+            compiled: HashMap::new(),
+        }
+    }
+
+    /// Declares a toggle like `USE_TEXTURE`. There's room for `MAX_FEATURES` of them,
+    /// and each name can only be declared once. `key` reports it when either isn't the case.
+    pub fn with_feature<F: Into<String>>(mut self, feature: F) -> ShaderVariants {
+        self.features.push(feature.into());
+        self
+    }
+
+    /// Include root and defines shared by every variant.
+    pub fn with_compile_options(mut self, options: CompileOptions) -> ShaderVariants {
+        self.options = options;
+        self
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn features(&self) -> &[String] {
+        &self.features
+    }
+
+    /// The key for a combination of features, in any order.
+    pub fn key(&self, enabled: &[&str]) -> Result<VariantKey, ShaderError> {
+        self.check_features()?;
+        let mut key = VariantKey::default();
+        for &feature in enabled {
+            match self.features.iter().position(|known| known == feature) {
+                Some(index) => key.0 |= 1 << index,
+                None => {
+                    return Err(ShaderError::UnknownFeature {
+                        path: self.path.clone(),
+                        feature: feature.to_string(),
+                        known: self.features.clone(),
+                    })
+                }
+            }
+        }
+        Ok(key)
+    }
+
+    fn check_features(&self) -> Result<(), ShaderError> {
+        if self.features.len() > MAX_FEATURES {
+            return Err(ShaderError::TooManyFeatures {
+                path: self.path.clone(),
+                count: self.features.len(),
+            });
+        }
+        for (index, feature) in self.features.iter().enumerate() {
+            if self.features[..index].contains(feature) {
+                return Err(ShaderError::DuplicateFeature {
+                    path: self.path.clone(),
+                    feature: feature.clone(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// The shared options plus a define for each feature `key` turns on.
+    /// Handy for a `PipelineDesc` that should build from one variant.
+    pub fn compile_options(&self, key: VariantKey) -> CompileOptions {
+        let mut options = self.options.clone();
+        for (index, feature) in self.features.iter().enumerate() {
+            if key.is_enabled(index) {
+                options = options.with_define(feature.as_str(), "1");
+            }
+        }
+        options
+    }
+
+    /// The variant for `key`, compiling it unless it already was.
+    /// `cache` keeps each variant on disk separately, so they survive restarts too.
+    pub fn variant(
+        &mut self,
+        device: Arc<Device>,
+        key: VariantKey,
+        cache: Option<&ShaderCache>,
+    ) -> Result<LoadedShader, ShaderError> {
+        if let Some(shader) = self.compiled.get(&key) {
+            return Ok(shader.clone());
+        }
+        let options = self.compile_options(key);
+        let shader = create_shader(device, &self.path, self.shader_type.clone(), &options, cache)?;
+        self.compiled.insert(key, shader.clone());
+        Ok(shader)
+    }
+
+    /// How many variants have been compiled so far.
+    pub fn compiled_count(&self) -> usize {
+        self.compiled.len()
+    }
+
+    /// Forgets every compiled variant if `path` is the shader or one of the files it includes,
+    /// so the next `variant` call recompiles. Returns whether anything was dropped.
+    pub fn invalidate_if_changed(&mut self, path: &Path) -> bool {
+        if self.compiled.is_empty() {
+            return false;
+        }
+        let canonical_path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        let own_path =
+            fs::canonicalize(&self.path).unwrap_or_else(|_| Path::new(&self.path).to_path_buf());
+        let changed = canonical_path == own_path
+            || self.compiled
+                .values()
+                .any(|shader| shader.includes.contains(&canonical_path));
+        if changed {
+            self.compiled.clear();
+        }
+        changed
+    }
+}
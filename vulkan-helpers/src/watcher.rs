@@ -0,0 +1,71 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Watches a shader directory and everything under it for changed files,
+/// so includes kept in subdirectories like `lib/` trigger reloads too.
+///
+/// Polls modification times rather than pulling in a file watching crate,
+/// which is plenty for a handful of shaders checked a few times a second.
+pub struct ShaderWatcher {
+    dir: PathBuf,
+    modified: HashMap<PathBuf, SystemTime>,
+    interval: Duration,
+    last_poll: Instant,
+}
+
+impl ShaderWatcher {
+    pub fn new<P: Into<PathBuf>>(dir: P) -> ShaderWatcher {
+        let mut watcher = ShaderWatcher {
+            dir: dir.into(),
+            modified: HashMap::new(),
+            interval: Duration::from_millis(250),
+            last_poll: Instant::now(),
+        };
+        watcher.scan();
+        watcher
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Files that were added or modified since the last poll.
+    /// Cheap to call every frame, the directory is only read every so often.
+    pub fn changed(&mut self) -> Vec<PathBuf> {
+        if self.last_poll.elapsed() < self.interval {
+            return vec![];
+        }
+        self.last_poll = Instant::now();
+        self.scan()
+    }
+
+    fn scan(&mut self) -> Vec<PathBuf> {
+        let mut changed = vec![];
+        let mut dirs = vec![self.dir.clone()];
+        while let Some(dir) = dirs.pop() {
+            let entries = match fs::read_dir(&dir) {
+                Ok(entries) => entries,
+                Err(_) => continue,
+            };
+            for entry in entries.filter_map(|entry| entry.ok()) {
+                let path = entry.path();
+                // NOTE: `file_type` doesn't follow symlinks, so a link back up the tree can't loop forever.
+                if entry.file_type().map(|file_type| file_type.is_dir()).unwrap_or(false) {
+                    dirs.push(path);
+                    continue;
+                }
+                let modified = match entry.metadata().and_then(|metadata| metadata.modified()) {
+                    Ok(modified) => modified,
+                    Err(_) => continue,
+                };
+                if self.modified.insert(path.clone(), modified) != Some(modified) {
+                    changed.push(path);
+                }
+            }
+        }
+        changed.sort();
+        changed
+    }
+}